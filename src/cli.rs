@@ -0,0 +1,559 @@
+use std::path::PathBuf;
+use clap::builder::{PossibleValuesParser, RangedU64ValueParser, TypedValueParser};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use web3::ethabi::Address;
+use web3::ethabi::ethereum_types::{H256, U256};
+use novax::config::{ContractOverrides, ProductionOverrides, ProfileOverrides, ResourcePrices};
+use novax::contracts::{Resource, Structure};
+use novax::gas_oracle::{parse_gas_amount, GasStrategy};
+use novax::keystore::{KeySource, Passphrase};
+use novax::utils::parse_decimals;
+use novax::tx::StuckPolicy;
+
+/// Automate and ease the management of your NovaX planets.
+#[derive(Parser, Debug)]
+#[command(version, about)]
+pub struct Cli {
+    /// Address of the wallet owning the planets (derived from the key when not given)
+    #[arg(long, global = true, value_name = "ADDRESS")]
+    pub wallet: Option<Address>,
+
+    #[command(flatten)]
+    pub key: KeyArgs,
+
+    #[command(flatten)]
+    pub config: ConfigArgs,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Display the pending resources, structure levels and production of every planet
+    FetchInfo(FetchInfoArgs),
+    /// Harvest the pending resources of every planet in a single transaction
+    Harvest(HarvestArgs),
+    /// Level up the structures of your planets that are below a given level
+    Upgrade(UpgradeArgs),
+    /// Mint new planets with createPlanet, paying the current planet fee in AVAX
+    MintPlanet(MintPlanetArgs),
+    /// Build the structures missing on your planets
+    Build(BuildArgs),
+    /// Move an amount of one resource from a planet to the wallet, without harvesting the rest
+    Withdraw(WithdrawArgs),
+    /// Transfer planets and manage who may transfer them
+    Planet {
+        #[command(subcommand)]
+        command: PlanetCommand,
+    },
+    /// Transfer resource tokens and manage their allowances
+    Token {
+        #[command(subcommand)]
+        command: TokenCommand,
+    },
+    /// Speed up or cancel a pending transaction of the wallet
+    Tx {
+        #[command(subcommand)]
+        command: TxCommand,
+    },
+    /// Manage the encrypted keystores of the keystore directory
+    Keystore {
+        #[command(subcommand)]
+        command: KeystoreCommand,
+    },
+    /// Generate a shell completion script and print it on stdout
+    Completions {
+        /// Shell to generate the completion script for
+        shell: Shell,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TxCommand {
+    /// Send a pending transaction again with the same nonce and fees bumped by --fee-bump
+    Speedup {
+        /// Hash of the pending transaction
+        hash: H256,
+
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Replace the pending transaction with this nonce by a zero-value transfer to the wallet itself
+    ///
+    /// The fees are the ones of the gas options (oracle or --gas-price), bumped by --fee-bump.
+    Cancel {
+        /// Nonce of the pending transaction
+        nonce: u64,
+
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum PlanetCommand {
+    /// Transfer planets of the wallet to another address, with safeTransferFrom by default
+    Transfer {
+        /// Address receiving the planets
+        to: Address,
+
+        /// Planets to transfer
+        #[arg(long, value_delimiter = ',', value_name = "IDS", required = true, value_parser = parse_planet_id)]
+        planets: Vec<U256>,
+
+        /// Hex encoded data passed to the recipient by safeTransferFrom
+        #[arg(long, value_name = "HEX", value_parser = parse_hex_data)]
+        data: Option<Vec<u8>>,
+
+        /// Use transferFrom, which does not check that a contract recipient accepts NFTs
+        #[arg(long, conflicts_with = "data")]
+        unchecked: bool,
+
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Allow an address to transfer some planets, or every planet, of the wallet
+    Approve {
+        /// Address allowed to transfer the planets
+        spender: Address,
+
+        /// Planets the address may transfer
+        #[arg(long, value_delimiter = ',', value_name = "IDS", required_unless_present = "all", value_parser = parse_planet_id)]
+        planets: Vec<U256>,
+
+        /// Make the address an operator of every planet of the wallet, present and future
+        #[arg(long, conflicts_with = "planets")]
+        all: bool,
+
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// List the addresses allowed to transfer planets of the wallet, and optionally revoke them
+    ///
+    /// Operators are found in the ApprovalForAll events of the wallet of the last 500000 blocks (about two weeks) :
+    /// use --from-block to search further back, and --operators to check known addresses.
+    Approvals {
+        /// First block searched for ApprovalForAll events [default: 500000 blocks before the latest one]
+        #[arg(long, value_name = "BLOCK")]
+        from_block: Option<u64>,
+
+        /// Operators to check in addition to the ones found in the events
+        #[arg(long, value_delimiter = ',', value_name = "ADDRESSES")]
+        operators: Vec<Address>,
+
+        /// Revoke every approval listed
+        #[arg(long)]
+        revoke: bool,
+
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TokenCommand {
+    /// Send resource tokens of the wallet to another address
+    Transfer {
+        /// Resource token : solar, metal or crystal
+        #[arg(value_parser = resource_parser())]
+        resource: Resource,
+
+        /// Address receiving the tokens
+        to: Address,
+
+        /// Amount to send, in tokens (e.g. `250.5`)
+        #[arg(value_parser = parse_token_amount)]
+        amount: U256,
+
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// Set the amount of resource tokens an address may spend from the wallet
+    Approve {
+        /// Resource token : solar, metal or crystal
+        #[arg(value_parser = resource_parser())]
+        resource: Resource,
+
+        /// Address allowed to spend the tokens
+        spender: Address,
+
+        /// Allowance, in tokens (e.g. `1000`), `0` revoking it
+        #[arg(value_parser = parse_token_amount)]
+        amount: U256,
+
+        /// Add the amount to the current allowance (increaseAllowance) instead of replacing it
+        #[arg(long, conflicts_with = "decrease")]
+        increase: bool,
+
+        /// Subtract the amount from the current allowance (decreaseAllowance) instead of replacing it
+        #[arg(long)]
+        decrease: bool,
+
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+    /// List the allowances the wallet has granted on the resource tokens, and optionally revoke them
+    ///
+    /// Spenders are found in the Approval events of the wallet of the last 500000 blocks (about two weeks) : use
+    /// --from-block to search further back, and --spenders to check known addresses.
+    Allowances {
+        /// First block searched for Approval events [default: 500000 blocks before the latest one]
+        #[arg(long, value_name = "BLOCK")]
+        from_block: Option<u64>,
+
+        /// Spenders to check in addition to the ones found in the events
+        #[arg(long, value_delimiter = ',', value_name = "ADDRESSES")]
+        spenders: Vec<Address>,
+
+        /// Revoke every allowance listed
+        #[arg(long)]
+        revoke: bool,
+
+        #[command(flatten)]
+        tx: TxArgs,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeystoreCommand {
+    /// Encrypt an existing private key into the keystore directory
+    ///
+    /// The key is read from --private-key-env / --private-key-fd when given, from a hidden prompt otherwise.
+    Import {
+        /// Name of the keystore file (the wallet address by default)
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Generate a new private key and store it encrypted in the keystore directory
+    New {
+        /// Name of the keystore file (the wallet address by default)
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// List the keystores of the keystore directory and their address
+    List,
+}
+
+/// Where the wallet key is loaded from. The private key is never taken on the command line itself.
+#[derive(Args, Debug)]
+pub struct KeyArgs {
+    /// Encrypted JSON keystore of the wallet, as a path or a name in the keystore directory
+    #[arg(long, global = true, value_name = "KEYSTORE", conflicts_with_all = ["private_key_env", "private_key_fd"])]
+    pub keystore: Option<String>,
+
+    /// File whose first line is the keystore passphrase (prompted otherwise)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub password_file: Option<PathBuf>,
+
+    /// Environment variable holding the hex encoded private key of the wallet
+    #[arg(long, global = true, value_name = "VAR", conflicts_with = "private_key_fd")]
+    pub private_key_env: Option<String>,
+
+    /// File descriptor the hex encoded private key of the wallet is read from (e.g. `3<key.txt`)
+    #[arg(long, global = true, value_name = "FD")]
+    pub private_key_fd: Option<i32>,
+}
+
+impl KeyArgs {
+    pub fn source(&self) -> Option<KeySource> {
+        if let Some(keystore) = &self.keystore {
+            Some(KeySource::Keystore(keystore.clone()))
+        } else if let Some(variable) = &self.private_key_env {
+            Some(KeySource::Env(variable.clone()))
+        } else {
+            self.private_key_fd.map(KeySource::Fd)
+        }
+    }
+
+    pub fn passphrase(&self) -> Passphrase {
+        match &self.password_file {
+            Some(path) => Passphrase::File(path.clone()),
+            None => Passphrase::Prompt,
+        }
+    }
+}
+
+/// Selection of the configuration profile, and overrides of any of its keys.
+#[derive(Args, Debug)]
+#[command(next_help_heading = "Configuration")]
+pub struct ConfigArgs {
+    /// Configuration file [default: ~/.config/novaxcli/config.toml]
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Profile to use (mainnet, fuji, local or any profile of the config file)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// WebSocket endpoint of the C-Chain node
+    #[arg(long, global = true, value_name = "URL")]
+    pub rpc_url: Option<String>,
+
+    /// Chain id the transactions are signed for
+    #[arg(long, global = true, value_name = "ID")]
+    pub chain_id: Option<u64>,
+
+    /// Address of the planet NFT contract
+    #[arg(long, global = true, value_name = "ADDRESS")]
+    pub planet_contract: Option<Address>,
+
+    /// Address of the game contract
+    #[arg(long, global = true, value_name = "ADDRESS")]
+    pub game_contract: Option<Address>,
+
+    /// Address of the metal ERC20 contract
+    #[arg(long, global = true, value_name = "ADDRESS")]
+    pub metal_contract: Option<Address>,
+
+    /// Address of the solar ERC20 contract
+    #[arg(long, global = true, value_name = "ADDRESS")]
+    pub solar_contract: Option<Address>,
+
+    /// Address of the crystal ERC20 contract
+    #[arg(long, global = true, value_name = "ADDRESS")]
+    pub crystal_contract: Option<Address>,
+
+    /// Address of the Multicall3 contract batching the reads
+    #[arg(long, global = true, value_name = "ADDRESS")]
+    pub multicall_contract: Option<Address>,
+
+    /// How many planets are queried at the same time [default: `concurrency` of the profile, or 8]
+    #[arg(long, global = true, value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub concurrency: Option<usize>,
+
+    /// Timeout of each request to the metadata server, in seconds [default: `metadata_timeout` of the profile, or 10]
+    #[arg(long, global = true, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub metadata_timeout: Option<u64>,
+
+    /// Retries of a failed request to the metadata server, at most 10 [default: `metadata_retries` of the profile, or 3]
+    #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u32).range(..=10))]
+    pub metadata_retries: Option<u32>,
+
+    /// Value of one solar token, in AVAX
+    #[arg(long, global = true, value_name = "AVAX")]
+    pub solar_price: Option<f64>,
+
+    /// Value of one metal token, in AVAX
+    #[arg(long, global = true, value_name = "AVAX")]
+    pub metal_price: Option<f64>,
+
+    /// Value of one crystal token, in AVAX
+    #[arg(long, global = true, value_name = "AVAX")]
+    pub crystal_price: Option<f64>,
+}
+
+impl ConfigArgs {
+    pub fn overrides(&self) -> ProfileOverrides {
+        ProfileOverrides {
+            inherits: None,
+            rpc_url: self.rpc_url.clone(),
+            chain_id: self.chain_id,
+            gas_price: None,
+            gas_strategy: None,
+            max_fee: None,
+            max_fee_wait: None,
+            priority_fee: None,
+            confirmations: None,
+            receipt_timeout: None,
+            on_stuck: None,
+            stuck_timeout: None,
+            fee_bump: None,
+            concurrency: self.concurrency,
+            metadata_timeout: self.metadata_timeout,
+            metadata_retries: self.metadata_retries,
+            contracts: ContractOverrides {
+                planet: self.planet_contract,
+                game: self.game_contract,
+                metal: self.metal_contract,
+                solar: self.solar_contract,
+                crystal: self.crystal_contract,
+                multicall: self.multicall_contract,
+            },
+            prices: ResourcePrices {
+                solar: self.solar_price,
+                metal: self.metal_price,
+                crystal: self.crystal_price,
+            },
+            production: ProductionOverrides::default(),
+        }
+    }
+}
+
+/// Arguments shared by every command sending a transaction.
+#[derive(Args, Debug)]
+pub struct TxArgs {
+    /// Send legacy transactions at this gas price (e.g. `25gwei`) instead of type-2 (EIP-1559) ones
+    #[arg(long, value_name = "AMOUNT", value_parser = parse_gas_amount, conflicts_with_all = ["gas_strategy", "max_fee", "priority_fee"])]
+    pub gas_price: Option<U256>,
+
+    /// How the gas oracle prices type-2 transactions : standard, fast or max=AMOUNT [default: `gas_strategy` of the profile, or standard]
+    #[arg(long, value_name = "STRATEGY")]
+    pub gas_strategy: Option<GasStrategy>,
+
+    /// Never pay more than this max fee per gas (e.g. `50gwei`), waiting while the base fee is above it [default: `max_fee` of the profile]
+    #[arg(long, value_name = "AMOUNT", value_parser = parse_gas_amount)]
+    pub max_fee: Option<U256>,
+
+    /// Give up waiting for the base fee to drop below --max-fee after this many seconds [default: `max_fee_wait` of the profile, or 600]
+    #[arg(long, value_name = "SECONDS")]
+    pub max_fee_wait: Option<u64>,
+
+    /// Priority fee per gas of type-2 transactions (e.g. `1gwei`) [default: `priority_fee` of the profile, or the one of the strategy]
+    #[arg(long, value_name = "AMOUNT", value_parser = parse_gas_amount)]
+    pub priority_fee: Option<U256>,
+
+    /// Number of blocks to wait for after a transaction is mined [default: `confirmations` of the profile, or 1]
+    #[arg(long, value_name = "BLOCKS")]
+    pub confirmations: Option<u64>,
+
+    /// Give up waiting for a transaction after this many seconds [default: `receipt_timeout` of the profile, or 300]
+    #[arg(long, value_name = "SECONDS")]
+    pub receipt_timeout: Option<u64>,
+
+    /// What to do with a transaction still pending after --stuck-timeout : wait, speed-up or cancel [default: `on_stuck` of the profile, or wait]
+    #[arg(long, value_name = "POLICY")]
+    pub on_stuck: Option<StuckPolicy>,
+
+    /// Seconds after which a pending transaction is considered stuck [default: `stuck_timeout` of the profile, or 60]
+    #[arg(long, value_name = "SECONDS")]
+    pub stuck_timeout: Option<u64>,
+
+    /// Fee increase of the replacement transactions, in percent [default: `fee_bump` of the profile, or 15]
+    #[arg(long, value_name = "PERCENT")]
+    pub fee_bump: Option<u64>,
+
+    /// Simulate the transactions and print what they would do, without signing or sending them
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args, Debug)]
+pub struct HarvestArgs {
+    /// Send the harvest again, with a fresh gas estimate, up to this many times when it reverts
+    #[arg(long, value_name = "COUNT", default_value_t = 0)]
+    pub resubmit: u32,
+
+    /// Only harvest these planets, instead of every planet of the wallet
+    #[arg(long, value_delimiter = ',', value_name = "IDS", value_parser = parse_planet_id)]
+    pub planets: Vec<U256>,
+
+    /// Skip the planets whose pending solar, metal and crystal are each below this amount of tokens (e.g. `500`)
+    #[arg(long, value_name = "AMOUNT", value_parser = parse_token_amount)]
+    pub min_pending: Option<U256>,
+
+    /// Skip the planets whose pending resources are worth less than the fee of harvesting them alone (needs the
+    /// resource prices of the profile, or --solar-price, --metal-price and --crystal-price)
+    #[arg(long)]
+    pub only_if_worth: bool,
+
+    /// Split the planets into several harvest transactions, each estimated to use at most this much gas
+    #[arg(long, value_name = "GAS", default_value_t = 8_000_000)]
+    pub max_chunk_gas: u64,
+
+    #[command(flatten)]
+    pub tx: TxArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct FetchInfoArgs {
+    /// Output format : text (sentences), table, json, ndjson (one object per line) or csv
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    pub format: OutputFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Table,
+    Json,
+    Ndjson,
+    Csv,
+}
+
+#[derive(Args, Debug)]
+pub struct UpgradeArgs {
+    /// Structures to level up : solar (solar panel), mine (metal mine), crystal (crystal laboratory)
+    #[arg(long, value_delimiter = ',', required = true, value_parser = structure_parser())]
+    pub structures: Vec<Structure>,
+
+    /// Only structures strictly below this level are leveled up (by one level)
+    #[arg(long, default_value_t = 2)]
+    pub max_level: u32,
+
+    #[command(flatten)]
+    pub tx: TxArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct MintPlanetArgs {
+    /// Number of planets to mint
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    pub count: u32,
+
+    /// Do not ask for a confirmation before sending the transaction
+    #[arg(long, short = 'y')]
+    pub yes: bool,
+
+    #[command(flatten)]
+    pub tx: TxArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct BuildArgs {
+    /// Structures to build where missing : solar (solar panel), mine (metal mine), crystal (crystal laboratory)
+    #[arg(long, value_delimiter = ',', default_values = ["solar", "mine", "crystal"], value_parser = structure_parser())]
+    pub structures: Vec<Structure>,
+
+    /// Only build on these planets, instead of every planet of the wallet
+    #[arg(long, value_delimiter = ',', value_name = "IDS", value_parser = parse_planet_id)]
+    pub planets: Vec<U256>,
+
+    /// AVAX sent along with each buildStructure transaction, when the game charges one (e.g. `0.1`)
+    #[arg(long, value_name = "AVAX", default_value = "0", value_parser = parse_token_amount)]
+    pub value: U256,
+
+    #[command(flatten)]
+    pub tx: TxArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct WithdrawArgs {
+    /// Resource to withdraw : solar, metal or crystal
+    #[arg(value_parser = resource_parser())]
+    pub resource: Resource,
+
+    /// Amount to withdraw, in tokens (e.g. `250.5`)
+    #[arg(value_parser = parse_token_amount)]
+    pub amount: U256,
+
+    /// Planet holding the pending resource
+    #[arg(long, value_name = "ID", value_parser = parse_planet_id)]
+    pub planet: U256,
+
+    #[command(flatten)]
+    pub tx: TxArgs,
+}
+
+fn structure_parser() -> impl TypedValueParser<Value = Structure> {
+    PossibleValuesParser::new(Structure::ALL.iter().map(|structure| structure.name()))
+        .map(|name| name.parse::<Structure>().unwrap())
+}
+
+fn resource_parser() -> impl TypedValueParser<Value = Resource> {
+    PossibleValuesParser::new(Resource::ALL.iter().map(|resource| resource.name()))
+        .map(|name| name.parse::<Resource>().unwrap())
+}
+
+fn parse_planet_id(value: &str) -> Result<U256, String> {
+    U256::from_dec_str(value).map_err(|err| format!("invalid planet id {} : {:?}", value, err))
+}
+
+fn parse_hex_data(value: &str) -> Result<Vec<u8>, String> {
+    hex::decode(value.trim_start_matches("0x")).map_err(|err| format!("invalid hex data {} : {}", value, err))
+}
+
+fn parse_token_amount(value: &str) -> Result<U256, String> {
+    parse_decimals(value).ok_or_else(|| format!("invalid amount {}, expected a decimal number such as 12.5", value))
+}
@@ -0,0 +1,499 @@
+use std::time::Duration;
+use secp256k1::SecretKey;
+use web3::Web3;
+use web3::futures::{stream, StreamExt, TryStreamExt};
+use web3::contract::tokens::Detokenize;
+use web3::ethabi::{Address, Event, RawLog};
+use web3::ethabi::ethereum_types::{H256, U256, U64};
+use web3::transports::WebSocket;
+use web3::types::{BlockNumber, CallRequest, FilterBuilder, Log};
+use crate::config::Profile;
+use crate::contracts::{Erc20, NovaxGame, NovaxPlanet, Resource, Resources, Structure};
+use crate::error::{NovaxError, Result};
+use crate::fees::FeePolicy;
+use crate::metadata::{default_cache_path, MetadataClient, MetadataSettings};
+use crate::multicall::{ContractRead, Multicall};
+use crate::tx::{simulate, Simulation, TxOutcome, TxRequest, TxSender, TxSettings};
+use crate::utils::{get_web3, is_out_of_gas, is_revert, ResponseApi};
+
+/// Planets queried at the same time unless set with [`NovaxClient::with_concurrency`].
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Widest block range queried at once with `eth_getLogs` when the node refuses to search the whole range.
+const LOG_BLOCK_RANGE: u64 = 2048;
+/// Blocks searched for events when no first block is given, about two weeks of C-Chain blocks.
+pub const DEFAULT_LOG_LOOKBACK: u64 = 500_000;
+
+/// Planets harvested together in one `harvestAll` transaction.
+#[derive(Debug, Clone)]
+pub struct HarvestChunk {
+    pub planet_ids: Vec<U256>,
+    /// Estimated gas of the transaction, `None` when it could not be estimated
+    pub gas: Option<U256>,
+}
+
+/// Connection to the NovaX contracts of a profile, optionally able to send transactions.
+pub struct NovaxClient {
+    web3: Web3<WebSocket>,
+    chain_id: u64,
+    planet: NovaxPlanet,
+    game: NovaxGame,
+    metal: Erc20,
+    solar: Erc20,
+    crystal: Erc20,
+    tx_settings: TxSettings,
+    sender: Option<TxSender>,
+    concurrency: usize,
+    multicall: Multicall,
+    metadata: MetadataClient,
+}
+
+impl NovaxClient {
+    /// Connect to the node of the profile. The client is read-only until a signer is set with [`NovaxClient::with_signer`].
+    pub async fn connect(profile: &Profile) -> Result<NovaxClient> {
+        let web3 = get_web3(&profile.rpc_url).await?;
+        let default = MetadataSettings::default();
+        let metadata_settings = MetadataSettings {
+            timeout: profile.metadata_timeout.map(Duration::from_secs).unwrap_or(default.timeout),
+            retries: profile.metadata_retries.unwrap_or(default.retries),
+        };
+        Ok(NovaxClient {
+            planet: NovaxPlanet::new(web3.eth(), profile.contracts.planet),
+            game: NovaxGame::new(web3.eth(), profile.contracts.game),
+            metal: Erc20::new(web3.eth(), profile.contracts.metal),
+            solar: Erc20::new(web3.eth(), profile.contracts.solar),
+            crystal: Erc20::new(web3.eth(), profile.contracts.crystal),
+            chain_id: profile.chain_id,
+            tx_settings: TxSettings::default(),
+            sender: None,
+            concurrency: profile.concurrency.unwrap_or(DEFAULT_CONCURRENCY),
+            multicall: Multicall::new(web3.clone(), profile.contracts.multicall),
+            metadata: MetadataClient::new(metadata_settings, Some(default_cache_path()))?,
+            web3,
+        })
+    }
+
+    /// Use `key` to sign the transactions, waiting for them as set by [`NovaxClient::with_tx_settings`].
+    pub fn with_signer(mut self, key: SecretKey) -> NovaxClient {
+        let sender = TxSender::new(self.web3.clone(), self.chain_id, key)
+            .with_settings(self.tx_settings)
+            .with_abi(self.planet.address(), self.planet.abi().clone())
+            .with_abi(self.game.address(), self.game.abi().clone())
+            .with_abi(self.metal.address(), self.metal.abi().clone())
+            .with_abi(self.solar.address(), self.solar.abi().clone())
+            .with_abi(self.crystal.address(), self.crystal.abi().clone());
+        self.sender = Some(sender);
+        self
+    }
+
+    /// Number of planets queried at the same time, [`DEFAULT_CONCURRENCY`] unless set by the profile.
+    pub fn with_concurrency(mut self, concurrency: usize) -> NovaxClient {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Confirmations and receipt timeout of the transactions sent by the signer.
+    pub fn with_tx_settings(mut self, settings: TxSettings) -> NovaxClient {
+        self.tx_settings = settings;
+        self.sender = self.sender.map(|sender| sender.with_settings(settings));
+        self
+    }
+
+    /// Address of the signer, if any.
+    pub fn signer_address(&self) -> Option<Address> {
+        self.sender.as_ref().map(TxSender::address)
+    }
+
+    /// Transaction pipeline of the signer, to send calls the client has no method for.
+    pub fn sender(&self) -> Result<&TxSender> {
+        self.sender.as_ref().ok_or_else(|| NovaxError::keystore("this client has no signer to send transactions"))
+    }
+
+    pub fn web3(&self) -> &Web3<WebSocket> {
+        &self.web3
+    }
+
+    pub fn planet_contract(&self) -> &NovaxPlanet {
+        &self.planet
+    }
+
+    pub fn game_contract(&self) -> &NovaxGame {
+        &self.game
+    }
+
+    /// ERC20 contract of a resource.
+    pub fn token(&self, resource: Resource) -> &Erc20 {
+        match resource {
+            Resource::Solar => &self.solar,
+            Resource::Metal => &self.metal,
+            Resource::Crystal => &self.crystal,
+        }
+    }
+
+    /// Ids of the planets owned by `owner`.
+    pub async fn planets_of(&self, owner: Address) -> Result<Vec<U256>> {
+        Ok(self.planet.tokens_of_owner(owner).await?)
+    }
+
+    /// Address allowed to transfer the planet on behalf of its owner, if any.
+    pub async fn planet_approval(&self, planet_id: U256) -> Result<Option<Address>> {
+        let approved = self.planet.get_approved(planet_id).await?;
+        Ok(Some(approved).filter(|approved| !approved.is_zero()))
+    }
+
+    /// Whether `operator` may transfer every planet of `owner`.
+    pub async fn is_planet_operator(&self, owner: Address, operator: Address) -> Result<bool> {
+        Ok(self.planet.is_approved_for_all(owner, operator).await?)
+    }
+
+    /// Operators currently allowed to transfer every planet of `owner`.
+    ///
+    /// The contract cannot enumerate them : the operators are found in the `ApprovalForAll` events emitted for
+    /// `owner` since `from_block`, then checked one by one with `isApprovedForAll`.
+    pub async fn planet_operators(&self, owner: Address, from_block: Option<u64>) -> Result<Vec<Address>> {
+        let event = self.planet.abi().event("ApprovalForAll")?;
+        let operators = self.approved_addresses(self.planet.address(), event, "operator", owner, from_block).await?;
+        let mut approved = Vec::new();
+        for operator in operators {
+            if self.is_planet_operator(owner, operator).await? {
+                approved.push(operator);
+            }
+        }
+        Ok(approved)
+    }
+
+    /// Spenders of `resource` with an allowance on the tokens of `owner`, and their allowance.
+    ///
+    /// Like for the planet operators, the spenders are found in the `Approval` events emitted for `owner` since
+    /// `from_block`, then their current allowance is read with `allowance`.
+    pub async fn token_allowances(&self, resource: Resource, owner: Address, from_block: Option<u64>) -> Result<Vec<(Address, U256)>> {
+        let token = self.token(resource);
+        let event = token.abi().event("Approval")?;
+        let spenders = self.approved_addresses(token.address(), event, "spender", owner, from_block).await?;
+        let mut allowances = Vec::new();
+        for spender in spenders {
+            let allowance = token.allowance(owner, spender).await?;
+            if !allowance.is_zero() {
+                allowances.push((spender, allowance));
+            }
+        }
+        Ok(allowances)
+    }
+
+    /// Amount `spender` may still transfer from the `resource` tokens of `owner`.
+    pub async fn token_allowance(&self, resource: Resource, owner: Address, spender: Address) -> Result<U256> {
+        Ok(self.token(resource).allowance(owner, spender).await?)
+    }
+
+    /// Distinct values of the address parameter `param` of the approval `event` emitted by `address` for `owner`.
+    async fn approved_addresses(&self, address: Address, event: &Event, param: &str, owner: Address, from_block: Option<u64>) -> Result<Vec<Address>> {
+        let mut approved: Vec<Address> = Vec::new();
+        for log in self.logs(address, event.signature(), H256::from(owner), from_block).await? {
+            let decoded = event.parse_log(RawLog { topics: log.topics, data: log.data.0 })?;
+            let value = decoded.params.into_iter().find(|decoded| decoded.name == param).and_then(|decoded| decoded.value.into_address());
+            if let Some(value) = value.filter(|value| !approved.contains(value)) {
+                approved.push(value);
+            }
+        }
+        Ok(approved)
+    }
+
+    /// Logs of `address` with the event `signature` and first indexed parameter `topic`, from `from_block` (the last
+    /// [`DEFAULT_LOG_LOOKBACK`] blocks when `None`) to the latest block. Falls back on windows of [`LOG_BLOCK_RANGE`]
+    /// blocks, at most `concurrency` of them queried at once, when the node refuses the whole range.
+    async fn logs(&self, address: Address, signature: H256, topic: H256, from_block: Option<u64>) -> Result<Vec<Log>> {
+        let filter = |from: u64, to: u64| {
+            FilterBuilder::default()
+                .address(vec![address])
+                .topics(Some(vec![signature]), Some(vec![topic]), None, None)
+                .from_block(BlockNumber::Number(U64::from(from)))
+                .to_block(BlockNumber::Number(U64::from(to)))
+                .build()
+        };
+        let latest = self.web3.eth().block_number().await?.as_u64();
+        let from_block = from_block.unwrap_or_else(|| latest.saturating_sub(DEFAULT_LOG_LOOKBACK));
+        match self.web3.eth().logs(filter(from_block, latest)).await {
+            Ok(logs) => return Ok(logs),
+            Err(web3::Error::Rpc(_)) => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        let windows = (from_block..=latest).step_by(LOG_BLOCK_RANGE as usize).map(|start| (start, latest.min(start + LOG_BLOCK_RANGE - 1)));
+        let logs: Vec<Vec<Log>> = stream::iter(windows.map(|(start, end)| self.web3.eth().logs(filter(start, end))))
+            .buffered(self.concurrency)
+            .try_collect()
+            .await?;
+        Ok(logs.into_iter().flatten().collect())
+    }
+
+    /// Metadata (name, coordinate, structure levels) of a planet.
+    pub async fn planet_metadata(&self, planet_id: U256) -> Result<ResponseApi> {
+        let metadata = self.metadata.fetch(&self.planet.token_uri(planet_id).await?).await;
+        self.save_metadata_cache();
+        metadata
+    }
+
+    /// Metadata of several planets, in the order of `planet_ids`. Their URIs are read in one batch, then at most
+    /// `concurrency` of them are fetched at once.
+    ///
+    /// A planet whose metadata cannot be fetched (and is not cached) does not fail the others : it is `None`, with a
+    /// warning on stderr.
+    pub async fn planets_metadata(&self, planet_ids: &[U256]) -> Result<Vec<Option<ResponseApi>>> {
+        let reads: Vec<_> = planet_ids.iter().map(|planet_id| self.planet.token_uri_read(*planet_id)).collect();
+        let planet_uris: Vec<String> = self.read_all(&reads).await?;
+        let metadata: Vec<_> = stream::iter(planet_uris.iter().map(|planet_uri| self.metadata.fetch(planet_uri))).buffered(self.concurrency).collect().await;
+        self.save_metadata_cache();
+        Ok(planet_ids
+            .iter()
+            .zip(metadata)
+            .map(|(planet_id, metadata)| {
+                metadata.map_err(|err| eprintln!("Planet {} -- metadata unavailable, skipped -- {}", planet_id, err)).ok()
+            })
+            .collect())
+    }
+
+    /// The cache only saves requests, failing to write it does not fail the command.
+    fn save_metadata_cache(&self) {
+        if let Err(err) = self.metadata.save_cache() {
+            eprintln!("Could not save the metadata cache -- {}", err);
+        }
+    }
+
+    /// Resources produced by a planet and not harvested yet.
+    pub async fn pending_resources(&self, planet_id: U256) -> Result<Resources> {
+        let amounts = self.read_all(&self.pending_resources_reads(planet_id)).await?;
+        Ok(Resources { solar: amounts[0], metal: amounts[1], crystal: amounts[2] })
+    }
+
+    /// Pending resources of several planets, in the order of `planet_ids`, read with one batch of `getResourceAmount`.
+    ///
+    /// `totalResourceOfPlanets` is not used : its ABI only says `uint256[]`, nothing tells how the amounts of each
+    /// planet and resource are laid out in it.
+    pub async fn pending_resources_of(&self, planet_ids: &[U256]) -> Result<Vec<Resources>> {
+        let reads: Vec<_> = planet_ids.iter().flat_map(|planet_id| self.pending_resources_reads(*planet_id)).collect();
+        let amounts: Vec<U256> = self.read_all(&reads).await?;
+        Ok(amounts.chunks(3).map(|amounts| Resources { solar: amounts[0], metal: amounts[1], crystal: amounts[2] }).collect())
+    }
+
+    fn pending_resources_reads(&self, planet_id: U256) -> Vec<ContractRead> {
+        Resource::ALL.iter().map(|resource| self.game.get_resource_amount_read(*resource, planet_id)).collect()
+    }
+
+    /// Resources held by `owner` in its wallet.
+    pub async fn balances(&self, owner: Address) -> Result<Resources> {
+        let reads: Vec<_> = Resource::ALL.iter().map(|resource| self.token(*resource).balance_of_read(owner)).collect();
+        let amounts = self.read_all(&reads).await?;
+        Ok(Resources { solar: amounts[0], metal: amounts[1], crystal: amounts[2] })
+    }
+
+    /// Resources needed to bring `structure` to `level`.
+    pub async fn upgrade_cost(&self, structure: Structure, level: u32) -> Result<Resources> {
+        Ok(self.game.resource_info(structure, level).await?)
+    }
+
+    /// Resources needed for each of the `(structure, level)` upgrades, read in one batch.
+    pub async fn upgrade_costs(&self, upgrades: &[(Structure, u32)]) -> Result<Vec<Resources>> {
+        let reads: Vec<_> = upgrades.iter().map(|(structure, level)| self.game.resource_info_read(*structure, *level)).collect();
+        let costs: Vec<Vec<U256>> = self.read_all(&reads).await?;
+        Ok(costs.iter().map(|cost| Resources::from_costs(cost)).collect::<web3::contract::Result<_>>()?)
+    }
+
+    /// Results of `reads`, batched through the multicall contract of the profile or JSON-RPC batch requests.
+    pub async fn read_all<T: Detokenize>(&self, reads: &[ContractRead]) -> Result<Vec<T>> {
+        let outputs = self.multicall.read(reads).await?;
+        Ok(outputs.into_iter().map(T::from_tokens).collect::<web3::contract::Result<_>>()?)
+    }
+
+    /// AVAX held by `owner`, in wei.
+    pub async fn avax_balance(&self, owner: Address) -> Result<U256> {
+        Ok(self.web3.eth().balance(owner, None).await?)
+    }
+
+    /// Price of one planet, in wei.
+    pub async fn planet_fee(&self) -> Result<U256> {
+        Ok(self.game.planet_fee().await?)
+    }
+
+    /// Number of planets that can ever be minted.
+    pub async fn max_planets(&self) -> Result<U256> {
+        Ok(self.planet.max_planets().await?)
+    }
+
+    /// Number of planets minted so far.
+    ///
+    /// The planet contract has no `totalSupply`, but planet ids are handed out in sequence from 1, so this is the
+    /// highest id `ownerOf` knows about, found by bisection between 0 and `MAX_PLANETS`.
+    pub async fn minted_planets(&self) -> Result<U256> {
+        let (mut low, mut high) = (U256::zero(), self.max_planets().await?);
+        while low < high {
+            let middle = low + (high - low + 1) / 2;
+            match self.planet.owner_of(middle).await {
+                Ok(_) => low = middle,
+                // Only a revert means the planet does not exist, a node error would give a wrong supply.
+                Err(web3::contract::Error::Api(err)) if is_revert(&err) => high = middle - 1,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(low)
+    }
+
+    /// Fail with [`NovaxError::InsufficientResources`] if `owner` does not hold `cost` in its wallet.
+    pub async fn ensure_resources(&self, owner: Address, cost: &Resources) -> Result<()> {
+        let available = self.balances(owner).await?;
+        if !available.covers(cost) {
+            return Err(NovaxError::InsufficientResources { needed: Box::new(*cost), available: Box::new(available) });
+        }
+        Ok(())
+    }
+
+    /// Harvest the pending resources of the planets in a single `harvestAll` transaction.
+    pub async fn harvest(&self, planet_ids: &[U256], fees: &FeePolicy) -> Result<TxOutcome> {
+        self.sender()?.send(&self.harvest_request(planet_ids), fees).await
+    }
+
+    /// Level up `structure` on the planet by one level.
+    pub async fn upgrade(&self, structure: Structure, planet_id: U256, fees: &FeePolicy) -> Result<TxOutcome> {
+        self.sender()?.send(&self.upgrade_request(structure, planet_id), fees).await
+    }
+
+    /// Mint `count` planets to the signer, paying `planet_fee` wei for each of them.
+    pub async fn mint_planets(&self, count: u32, planet_fee: U256, fees: &FeePolicy) -> Result<TxOutcome> {
+        self.sender()?.send(&self.mint_request(count, planet_fee), fees).await
+    }
+
+    /// Build the first level of `structure` on the planet, paying `value` wei along with the resources.
+    pub async fn build(&self, structure: Structure, planet_id: U256, value: U256, fees: &FeePolicy) -> Result<TxOutcome> {
+        self.sender()?.send(&self.build_request(structure, planet_id, value), fees).await
+    }
+
+    /// Move `amount` of `resource` from the pending resources of the planet to the wallet.
+    ///
+    /// Fails with [`NovaxError::InsufficientResources`], without sending anything, when the planet holds less than
+    /// `amount` of the resource.
+    pub async fn withdraw(&self, resource: Resource, amount: U256, planet_id: U256, fees: &FeePolicy) -> Result<TxOutcome> {
+        self.ensure_pending(resource, amount, planet_id).await?;
+        self.sender()?.send(&self.withdraw_request(resource, amount, planet_id), fees).await
+    }
+
+    /// Fail with [`NovaxError::InsufficientResources`] if the planet holds less than `amount` of pending `resource`.
+    pub async fn ensure_pending(&self, resource: Resource, amount: U256, planet_id: U256) -> Result<()> {
+        let pending = self.game.get_resource_amount(resource, planet_id).await?;
+        if pending < amount {
+            return Err(NovaxError::InsufficientResources {
+                needed: Box::new(Resources::only(resource, amount)),
+                available: Box::new(Resources::only(resource, pending)),
+            });
+        }
+        Ok(())
+    }
+
+    /// Submit all the requests back to back and wait for each of them, see [`TxSender::send_batch`].
+    pub async fn send_batch(&self, requests: &[TxRequest], fees: &FeePolicy) -> Result<Vec<Result<TxOutcome>>> {
+        Ok(self.sender()?.send_batch(requests, fees).await)
+    }
+
+    /// Split the planets into chunks whose `harvestAll` is estimated to use at most `max_gas`, halving every chunk
+    /// that does not fit, reverts or runs out of gas. A planet that does not fit alone, or whose harvest reverts or
+    /// runs out of gas, gets its own chunk. Any other estimation error is returned as is.
+    pub async fn harvest_chunks(&self, from: Address, planet_ids: &[U256], max_gas: U256) -> Result<Vec<HarvestChunk>> {
+        let mut chunks = Vec::new();
+        let mut to_estimate = vec![planet_ids.to_vec()];
+        while let Some(planet_ids) = to_estimate.pop() {
+            if planet_ids.is_empty() {
+                continue;
+            }
+            match self.estimate_harvest(from, &planet_ids).await {
+                Ok(gas) if gas <= max_gas || planet_ids.len() == 1 => chunks.push(HarvestChunk { planet_ids, gas: Some(gas) }),
+                // A node or transport error says nothing about the size of the chunk.
+                Err(err) if !is_revert(&err) && !is_out_of_gas(&err) => return Err(err.into()),
+                Err(_) if planet_ids.len() == 1 => chunks.push(HarvestChunk { planet_ids, gas: None }),
+                Ok(_) | Err(_) => {
+                    // The first half is pushed last so the chunks keep the order of the planets.
+                    let (first, second) = planet_ids.split_at(planet_ids.len() / 2);
+                    to_estimate.push(second.to_vec());
+                    to_estimate.push(first.to_vec());
+                }
+            }
+        }
+        Ok(chunks)
+    }
+
+    /// Gas used by a `harvestAll` transaction of `planet_ids` sent by `from`.
+    pub async fn estimate_harvest(&self, from: Address, planet_ids: &[U256]) -> std::result::Result<U256, web3::Error> {
+        let request = self.harvest_request(planet_ids);
+        let call = CallRequest { from: Some(from), to: Some(request.to), data: Some(request.data), ..CallRequest::default() };
+        self.web3.eth().estimate_gas(call, None).await
+    }
+
+    pub fn harvest_request(&self, planet_ids: &[U256]) -> TxRequest {
+        TxRequest::new(self.game.address(), self.game.harvest_all(planet_ids), "Harvest All tx")
+    }
+
+    pub fn mint_request(&self, count: u32, planet_fee: U256) -> TxRequest {
+        let value = planet_fee.saturating_mul(U256::from(count));
+        TxRequest::new(self.game.address(), self.game.create_planet(U256::from(count)), format!("Mint {} planet(s) tx", count)).with_value(value)
+    }
+
+    pub fn token_transfer_request(&self, resource: Resource, to: Address, amount: U256) -> TxRequest {
+        let token = self.token(resource);
+        TxRequest::new(token.address(), token.transfer(to, amount), format!("Transfer of {} tx to {:?}", resource, to))
+    }
+
+    /// `approve` of `amount` tokens to `spender`, zero revoking its allowance.
+    pub fn token_approve_request(&self, resource: Resource, spender: Address, amount: U256) -> TxRequest {
+        let token = self.token(resource);
+        TxRequest::new(token.address(), token.approve(spender, amount), format!("Approval of {} tx to {:?}", resource, spender))
+    }
+
+    /// `increaseAllowance` (or `decreaseAllowance`) of the allowance of `spender` by `amount` tokens.
+    pub fn token_allowance_change_request(&self, resource: Resource, spender: Address, amount: U256, increase: bool) -> TxRequest {
+        let token = self.token(resource);
+        let (data, action) = match increase {
+            true => (token.increase_allowance(spender, amount), "Increase"),
+            false => (token.decrease_allowance(spender, amount), "Decrease"),
+        };
+        TxRequest::new(token.address(), data, format!("{} of the {} allowance tx of {:?}", action, resource, spender))
+    }
+
+    /// `transferFrom` of a planet, which does not check that a contract recipient accepts NFTs.
+    pub fn planet_transfer_request(&self, from: Address, to: Address, planet_id: U256) -> TxRequest {
+        TxRequest::new(self.planet.address(), self.planet.transfer_from(from, to, planet_id), format!("Transfer of planet {} tx", planet_id))
+    }
+
+    /// `safeTransferFrom` of a planet, passing `data` to the recipient when given.
+    pub fn planet_safe_transfer_request(&self, from: Address, to: Address, planet_id: U256, data: Option<&[u8]>) -> TxRequest {
+        let calldata = self.planet.safe_transfer_from(from, to, planet_id, data);
+        TxRequest::new(self.planet.address(), calldata, format!("Safe transfer of planet {} tx", planet_id))
+    }
+
+    /// `approve` of a planet to `to`, the zero address revoking its approval.
+    pub fn planet_approve_request(&self, to: Address, planet_id: U256) -> TxRequest {
+        TxRequest::new(self.planet.address(), self.planet.approve(to, planet_id), format!("Approval of planet {} tx", planet_id))
+    }
+
+    /// `setApprovalForAll` of `operator` over every planet of the signer.
+    pub fn planet_operator_request(&self, operator: Address, approved: bool) -> TxRequest {
+        let action = if approved { "Approval" } else { "Revocation" };
+        TxRequest::new(self.planet.address(), self.planet.set_approval_for_all(operator, approved), format!("{} of operator {:?} tx", action, operator))
+    }
+
+    pub fn build_request(&self, structure: Structure, planet_id: U256, value: U256) -> TxRequest {
+        let data = self.game.build_structure(structure, planet_id);
+        TxRequest::new(self.game.address(), data, format!("Build {} tx on planet {}", structure, planet_id)).with_value(value)
+    }
+
+    pub fn withdraw_request(&self, resource: Resource, amount: U256, planet_id: U256) -> TxRequest {
+        let data = self.game.withdraw_resource(resource, amount, planet_id);
+        TxRequest::new(self.game.address(), data, format!("Withdraw {} tx from planet {}", resource, planet_id))
+    }
+
+    pub fn upgrade_request(&self, structure: Structure, planet_id: U256) -> TxRequest {
+        let data = self.game.level_up_structure(structure, planet_id);
+        TxRequest::new(self.game.address(), data, format!("Level up {} tx on planet {}", structure, planet_id))
+    }
+
+    /// Simulate `request` sent by `from`, without signing or sending it.
+    pub async fn simulate(&self, from: Address, request: &TxRequest, fees: &FeePolicy) -> Result<Simulation> {
+        simulate(&self.web3, from, request, fees).await
+    }
+}
@@ -0,0 +1,107 @@
+use web3::ethabi::Address;
+use web3::ethabi::ethereum_types::U256;
+use novax::client::NovaxClient;
+use novax::fees::FeePolicy;
+use novax::error::{NovaxError, Result};
+use novax::contracts::Structure;
+use novax::utils::to_decimals;
+use crate::cli::BuildArgs;
+use crate::commands::simulation::print_simulation;
+
+/// Build the `--structures` missing (at level 0) on the planets of the wallet, or on the `--planets` only.
+///
+/// Every planet lacking some structures is reported first. The builds are then planned against a local copy of the
+/// wallet balances and the level 1 cost of each structure (`resourceInfo`, read in one batch), and sent back to back
+/// with `--value` AVAX each. Builds the wallet cannot afford are skipped and reported as an
+/// [`NovaxError::InsufficientResources`] error once the others have been sent, unless a build transaction failed.
+/// Nothing is sent, and [`NovaxError::InsufficientFunds`] is returned, when the AVAX balance does not cover the value
+/// and the gas of every planned build. With `--dry-run` the planned builds are simulated instead of being sent.
+pub async fn build_structures(client: &NovaxClient, wallet_address: Address, planets_for_address: Vec<U256>, args: &BuildArgs, fees: &FeePolicy) -> Result<()> {
+    let planets = if args.planets.is_empty() {
+        planets_for_address
+    } else {
+        if let Some(planet_id) = args.planets.iter().find(|planet_id| !planets_for_address.contains(planet_id)) {
+            return Err(NovaxError::config(format!("planet {} is not owned by {:?}", planet_id, wallet_address)));
+        }
+        args.planets.clone()
+    };
+    let structures: Vec<_> = Structure::ALL.iter().copied().filter(|structure| args.structures.contains(structure)).collect();
+
+    let mut missing = Vec::new();
+    let metadata = client.planets_metadata(&planets).await?;
+    for (planet_id, planet) in planets.into_iter().zip(metadata) {
+        let planet = match planet {
+            Some(planet) => planet,
+            None => continue,
+        };
+        let lacking: Vec<_> = structures.iter().copied().filter(|structure| planet.structure_level(*structure) == 0).collect();
+        if lacking.is_empty() {
+            println!("Planet {} -- every structure is built", planet_id);
+        } else {
+            let names: Vec<_> = lacking.iter().map(|structure| structure.to_string()).collect();
+            println!("Planet {} -- missing {}", planet_id, names.join(", "));
+            missing.extend(lacking.into_iter().map(|structure| (structure, planet_id)));
+        }
+    }
+    if missing.is_empty() {
+        println!("Nothing to build");
+        return Ok(());
+    }
+
+    let levels: Vec<_> = structures.iter().map(|structure| (*structure, 1)).collect();
+    let costs: Vec<_> = structures.into_iter().zip(client.upgrade_costs(&levels).await?).collect();
+    for (structure, cost) in &costs {
+        println!("Cost for building {} -- {} + {} AVAX", structure, cost, to_decimals(args.value));
+    }
+
+    let mut balances = client.balances(wallet_address).await?;
+    let mut insufficient_resources = None;
+    let mut builds = Vec::new();
+    for (structure, planet_id) in missing {
+        let cost = costs.iter().find(|(built, _)| *built == structure).map(|(_, cost)| *cost).unwrap_or_default();
+        if !balances.covers(&cost) {
+            let err = NovaxError::InsufficientResources { needed: Box::new(cost), available: Box::new(balances) };
+            println!("We don't have enough resources to build {} on planet {} -- {}", structure, planet_id, err);
+            insufficient_resources = Some(err);
+            continue;
+        }
+
+        balances = balances.saturating_sub(&cost);
+        println!("Build {} on planet {} -- {} spent, {} left", structure, planet_id, cost, balances);
+        builds.push((structure, planet_id));
+    }
+
+    let requests: Vec<_> = builds.iter().map(|(structure, planet_id)| client.build_request(*structure, *planet_id, args.value)).collect();
+    if let Some(first) = requests.first() {
+        // Every build uses about the gas of the first one.
+        let simulation = client.simulate(wallet_address, first, fees).await?;
+        let gas_cost = simulation.gas.as_ref().map(|gas| gas.saturating_mul(simulation.fees.max_price())).unwrap_or_default();
+        let needed = args.value.saturating_add(gas_cost).saturating_mul(U256::from(requests.len()));
+        let available = client.avax_balance(wallet_address).await?;
+        println!("Sending {} build(s) for at most {} AVAX, {} AVAX available", requests.len(), to_decimals(needed), to_decimals(available));
+        if available < needed {
+            return Err(NovaxError::InsufficientFunds { needed, available });
+        }
+    }
+    let mut failure = None;
+    if args.tx.dry_run {
+        for request in &requests {
+            print_simulation(&client.simulate(wallet_address, request, fees).await?);
+        }
+    } else {
+        for ((structure, planet_id), outcome) in builds.iter().zip(client.send_batch(&requests, fees).await?) {
+            match outcome {
+                Ok(outcome) => println!("{} built on planet {} -- tx {:?}", structure, planet_id, outcome.receipt.transaction_hash),
+                Err(err) => {
+                    println!("Build of {} on planet {} failed -- {}", structure, planet_id, err);
+                    failure = Some(err);
+                }
+            }
+        }
+    }
+
+    match failure.or(insufficient_resources) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,317 @@
+use serde::Serialize;
+use web3::futures::try_join;
+use web3::ethabi::Address;
+use web3::ethabi::ethereum_types::U256;
+use novax::client::NovaxClient;
+use novax::contracts::{Resources, Structure};
+use novax::error::{NovaxError, Result};
+use novax::production::{ProductionRates, ProductionTable};
+use novax::utils::format_decimals;
+use crate::cli::OutputFormat;
+
+/// Report of `fetch-info`, the schema of the `json` format.
+///
+/// Amounts are decimal strings in tokens (e.g. `"12.5"`) so they keep their 18 decimals exactly, production rates
+/// are numbers of tokens per second. A planet whose metadata could not be fetched has a `null` name, coordinate,
+/// levels and production, and its production is left out of the total one; its pending resources are always counted.
+#[derive(Serialize, Debug)]
+struct Report {
+    wallet: Address,
+    planets: Vec<PlanetReport>,
+    /// Pending resources of all the planets
+    pending: Amounts,
+    /// Resource tokens held by the wallet
+    balances: Amounts,
+    /// Balances plus pending resources
+    total: Amounts,
+    /// Production of all the planets with known levels
+    production: ProductionRates,
+}
+
+#[derive(Serialize, Debug)]
+struct PlanetReport {
+    /// Token id, as a decimal string
+    id: String,
+    /// `None` (`null`) when the metadata of the planet is unavailable, like the coordinate, levels and production
+    name: Option<String>,
+    coordinate: Option<String>,
+    levels: Option<Levels>,
+    pending: Amounts,
+    production: Option<ProductionRates>,
+}
+
+#[derive(Serialize, Debug)]
+struct Levels {
+    solar: u32,
+    mine: u32,
+    crystal: u32,
+}
+
+#[derive(Serialize, Debug)]
+struct Amounts {
+    solar: String,
+    metal: String,
+    crystal: String,
+}
+
+/// Fetch the pending resources, structure levels and production of every planet, the production of all the planets
+/// and the wallet balances, and print them in `format`.
+pub async fn fetch_info(client: &NovaxClient, planets_for_address: Vec<U256>, wallet_address: Address, production: &ProductionTable, format: OutputFormat) -> Result<()> {
+    let mut total_pending = Resources::default();
+    let mut total_production = ProductionRates::default();
+    let mut planets = Vec::new();
+
+    // The metadata of the planets are fetched concurrently, their pending solar / metal / crystal and the balances of
+    // the wallet are each read in one batch.
+    let (metadata, pending_resources, balances) = try_join!(
+        client.planets_metadata(&planets_for_address),
+        client.pending_resources_of(&planets_for_address),
+        client.balances(wallet_address),
+    )?;
+    for ((planet_id, planet), pending) in planets_for_address.into_iter().zip(metadata).zip(pending_resources) {
+        // We add the amount of 'pending' resource of this planet to the total amount of pending resources across ALL planets.
+        total_pending = total_pending + pending;
+
+        // Without its metadata the levels of the planet are unknown, and so is its production.
+        let report = match planet {
+            Some(planet) => {
+                let rates = production.planet_rates(&planet);
+                total_production = total_production + rates;
+                PlanetReport {
+                    id: planet_id.to_string(),
+                    levels: Some(Levels {
+                        solar: planet.structure_level(Structure::Solar),
+                        mine: planet.structure_level(Structure::Mine),
+                        crystal: planet.structure_level(Structure::Crystal),
+                    }),
+                    pending: Amounts::from(pending),
+                    production: Some(rates),
+                    name: Some(planet.name),
+                    coordinate: Some(planet.coordinate),
+                }
+            }
+            None => PlanetReport { id: planet_id.to_string(), name: None, coordinate: None, levels: None, pending: Amounts::from(pending), production: None },
+        };
+        planets.push(report);
+    }
+
+    let report = Report {
+        wallet: wallet_address,
+        planets,
+        pending: Amounts::from(total_pending),
+        balances: Amounts::from(balances),
+        total: Amounts::from(balances + total_pending),
+        production: total_production,
+    };
+
+    match format {
+        OutputFormat::Text => print_text(&report),
+        OutputFormat::Table => print_table(&report),
+        OutputFormat::Json => println!("{}", to_json(&report)?),
+        OutputFormat::Ndjson => print_ndjson(&report)?,
+        OutputFormat::Csv => print_csv(&report),
+    }
+    Ok(())
+}
+
+fn print_text(report: &Report) {
+    for planet in &report.planets {
+        match (&planet.name, &planet.levels, &planet.production) {
+            (Some(name), Some(levels), Some(production)) => {
+                println!("Planet {} has {} metal (mine lvl {}), {} solar (solar panel lvl {}) and {} crystal (laboratory lvl {})", name, planet.pending.metal, levels.mine, planet.pending.solar, levels.solar, planet.pending.crystal, levels.crystal);
+                print_production(&format!("Planet {} produces", name), production);
+            }
+            _ => println!("Planet #{} has {} metal, {} solar and {} crystal (metadata unavailable)", planet.id, planet.pending.metal, planet.pending.solar, planet.pending.crystal),
+        }
+    }
+
+    println!("In total you have {} metal, {} solar and {} crystal pending across your planetes", report.pending.metal, report.pending.solar, report.pending.crystal);
+    print_production("In total you produce", &report.production);
+
+    println!("In Total you have {} metal, {} solar and {} crystal in your wallet + pending resources", report.total.metal, report.total.solar, report.total.crystal);
+}
+
+fn print_production(label: &str, rates: &ProductionRates) {
+    let (minute, hour, day) = (rates.over(60.), rates.over(3600.), rates.over(86400.));
+    println!("{} {} c/s || {} c/m || {} c/h || {} c/d", label, rates.crystal, minute.crystal, hour.crystal, day.crystal);
+    println!("{} {} m/s || {} m/m || {} m/h || {} m/d", label, rates.metal, minute.metal, hour.metal, day.metal);
+    println!("{} {} s/s || {} s/m || {} s/h || {} s/d", label, rates.solar, minute.solar, hour.solar, day.solar);
+}
+
+/// One row per planet, then the totals. The production columns are per day.
+fn print_table(report: &Report) {
+    let mut rows = vec![[
+        "id", "name", "coordinate", "solar lvl", "mine lvl", "crystal lvl", "pending solar", "pending metal", "pending crystal", "solar/day", "metal/day", "crystal/day",
+    ].map(String::from)];
+    for planet in &report.planets {
+        let levels = planet.levels.as_ref();
+        let production = planet.production.as_ref();
+        rows.push([
+            planet.id.clone(), planet.name.clone().unwrap_or_default(), planet.coordinate.clone().unwrap_or_default(),
+            or_empty(levels.map(|levels| levels.solar)), or_empty(levels.map(|levels| levels.mine)), or_empty(levels.map(|levels| levels.crystal)),
+            planet.pending.solar.clone(), planet.pending.metal.clone(), planet.pending.crystal.clone(),
+            or_empty(production.map(|rates| per_day(rates.solar))), or_empty(production.map(|rates| per_day(rates.metal))), or_empty(production.map(|rates| per_day(rates.crystal))),
+        ]);
+    }
+    let totals = [("pending", &report.pending), ("wallet", &report.balances), ("total", &report.total)];
+    for (label, amounts) in totals {
+        let mut row: [String; 12] = Default::default();
+        row[1] = label.to_string();
+        row[6..9].clone_from_slice(&[amounts.solar.clone(), amounts.metal.clone(), amounts.crystal.clone()]);
+        if label == "pending" {
+            row[9..12].clone_from_slice(&[per_day(report.production.solar), per_day(report.production.metal), per_day(report.production.crystal)]);
+        }
+        rows.push(row);
+    }
+
+    let widths: Vec<usize> = (0..12).map(|column| rows.iter().map(|row| row[column].chars().count()).max().unwrap_or(0)).collect();
+    for row in &rows {
+        let cells: Vec<String> = row.iter().zip(&widths).map(|(cell, width)| format!("{:<width$}", cell, width = width)).collect();
+        println!("{}", cells.join("  ").trim_end());
+    }
+}
+
+fn per_day(rate: f64) -> String {
+    format!("{}", rate * 86400.)
+}
+
+/// Cell of an unknown value : empty.
+fn or_empty<T: ToString>(value: Option<T>) -> String {
+    value.map(|value| value.to_string()).unwrap_or_default()
+}
+
+/// One object per line : a `planet` object per planet, then a `wallet` object with the balances and totals.
+fn print_ndjson(report: &Report) -> Result<()> {
+    for planet in &report.planets {
+        println!("{}", to_json_line("planet", planet)?);
+    }
+    #[derive(Serialize)]
+    struct Wallet<'a> {
+        wallet: Address,
+        pending: &'a Amounts,
+        balances: &'a Amounts,
+        total: &'a Amounts,
+        production: &'a ProductionRates,
+    }
+    let wallet = Wallet { wallet: report.wallet, pending: &report.pending, balances: &report.balances, total: &report.total, production: &report.production };
+    println!("{}", to_json_line("wallet", &wallet)?);
+    Ok(())
+}
+
+/// `kind` is `planet` for the planet rows, `pending`, `wallet` and `total` for the totals.
+fn print_csv(report: &Report) {
+    println!("kind,id,name,coordinate,solar_level,mine_level,crystal_level,solar,metal,crystal,solar_per_second,metal_per_second,crystal_per_second");
+    for planet in &report.planets {
+        let levels = planet.levels.as_ref();
+        let production = planet.production.as_ref();
+        println!(
+            "planet,{},{},{},{},{},{},{},{},{},{},{},{}",
+            planet.id, csv_field(planet.name.as_deref().unwrap_or_default()), csv_field(planet.coordinate.as_deref().unwrap_or_default()),
+            or_empty(levels.map(|levels| levels.solar)), or_empty(levels.map(|levels| levels.mine)), or_empty(levels.map(|levels| levels.crystal)),
+            planet.pending.solar, planet.pending.metal, planet.pending.crystal,
+            or_empty(production.map(|rates| rates.solar)), or_empty(production.map(|rates| rates.metal)), or_empty(production.map(|rates| rates.crystal)),
+        );
+    }
+    let production = &report.production;
+    println!("pending,,,,,,,{},{},{},{},{},{}", report.pending.solar, report.pending.metal, report.pending.crystal, production.solar, production.metal, production.crystal);
+    println!("wallet,{:?},,,,,,{},{},{},,,", report.wallet, report.balances.solar, report.balances.metal, report.balances.crystal);
+    println!("total,,,,,,,{},{},{},,,", report.total.solar, report.total.metal, report.total.crystal);
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn to_json(report: &Report) -> Result<String> {
+    serde_json::to_string_pretty(report).map_err(|err| NovaxError::Io(err.into()))
+}
+
+/// `value` as a single line JSON object, with a leading `type` field.
+fn to_json_line<T: Serialize>(kind: &str, value: &T) -> Result<String> {
+    #[derive(Serialize)]
+    struct Tagged<'a, T> {
+        #[serde(rename = "type")]
+        kind: &'a str,
+        #[serde(flatten)]
+        value: &'a T,
+    }
+    serde_json::to_string(&Tagged { kind, value }).map_err(|err| NovaxError::Io(err.into()))
+}
+
+impl From<Resources> for Amounts {
+    fn from(resources: Resources) -> Self {
+        Amounts { solar: format_decimals(resources.solar), metal: format_decimals(resources.metal), crystal: format_decimals(resources.crystal) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("Earth"), "Earth");
+        assert_eq!(csv_field("12:34"), "12:34");
+        assert_eq!(csv_field("Earth, Mars"), "\"Earth, Mars\"");
+        assert_eq!(csv_field("the \"red\" one"), "\"the \"\"red\"\" one\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+        assert_eq!(csv_field("carriage\rreturn"), "\"carriage\rreturn\"");
+    }
+
+    #[test]
+    fn report_serializes_to_the_documented_schema() {
+        let amounts = |solar: u64| Amounts::from(Resources { solar: U256::exp10(18) * solar, metal: U256::exp10(17) * 5, crystal: U256::zero() });
+        let production = ProductionRates { solar: 0.001, metal: 0.002, crystal: 0. };
+        let report = Report {
+            wallet: Address::from_low_u64_be(1),
+            planets: vec![
+                PlanetReport {
+                    id: "12".to_string(),
+                    name: Some("Earth".to_string()),
+                    coordinate: Some("1:2".to_string()),
+                    levels: Some(Levels { solar: 1, mine: 2, crystal: 0 }),
+                    pending: amounts(1),
+                    production: Some(production),
+                },
+                PlanetReport { id: "13".to_string(), name: None, coordinate: None, levels: None, pending: amounts(0), production: None },
+            ],
+            pending: amounts(1),
+            balances: amounts(2),
+            total: amounts(3),
+            production,
+        };
+
+        let production = json!({ "solar": 0.001, "metal": 0.002, "crystal": 0.0 });
+        assert_eq!(serde_json::to_value(&report).unwrap(), json!({
+            "wallet": "0x0000000000000000000000000000000000000001",
+            "planets": [{
+                "id": "12",
+                "name": "Earth",
+                "coordinate": "1:2",
+                "levels": { "solar": 1, "mine": 2, "crystal": 0 },
+                "pending": { "solar": "1", "metal": "0.5", "crystal": "0" },
+                "production": production,
+            }, {
+                "id": "13",
+                "name": null,
+                "coordinate": null,
+                "levels": null,
+                "pending": { "solar": "0", "metal": "0.5", "crystal": "0" },
+                "production": null,
+            }],
+            "pending": { "solar": "1", "metal": "0.5", "crystal": "0" },
+            "balances": { "solar": "2", "metal": "0.5", "crystal": "0" },
+            "total": { "solar": "3", "metal": "0.5", "crystal": "0" },
+            "production": production,
+        }));
+
+        let line = to_json_line("planet", &report.planets[0]).unwrap();
+        assert!(!line.contains('\n'));
+        assert!(line.starts_with("{\"type\":\"planet\",\"id\":\"12\""), "{}", line);
+    }
+}
@@ -0,0 +1,150 @@
+use web3::ethabi::Address;
+use web3::ethabi::ethereum_types::U256;
+use novax::client::NovaxClient;
+use novax::contracts::{Resource, Resources};
+use novax::config::ResourcePrices;
+use novax::fees::FeePolicy;
+use novax::error::{NovaxError, Result};
+use novax::utils::{format_decimals, to_decimals};
+use crate::cli::HarvestArgs;
+use crate::commands::receipt::print_receipt;
+use crate::commands::simulation::print_simulation;
+
+/// Harvest the planets of the wallet with `harvestAll` transactions, and print their receipts.
+///
+/// Only the planets given with `--planets` are harvested when set, minus those skipped by `--min-pending` and
+/// `--only-if-worth` (see [`select_planets`]).
+///
+/// The planets are split into chunks whose transaction fits in `--max-chunk-gas`, each chunk being sent and reported
+/// on its own; a failed chunk does not stop the next ones, the last failure is returned once they have all been
+/// processed. With `--dry-run`, print the resources each planet would send to the wallet and the simulated
+/// transactions instead.
+pub async fn harvest_all(client: &NovaxClient, wallet_address: Address, planets_for_address: Vec<U256>, fees: &FeePolicy, prices: &ResourcePrices, args: &HarvestArgs) -> Result<()> {
+    let planets_for_address = select_planets(client, wallet_address, planets_for_address, fees, prices, args).await?;
+    if planets_for_address.is_empty() {
+        println!("No planet to harvest");
+        return Ok(());
+    }
+
+    let chunks = client.harvest_chunks(wallet_address, &planets_for_address, U256::from(args.max_chunk_gas)).await?;
+    println!("{} planets to harvest in {} transaction(s)", planets_for_address.len(), chunks.len());
+    for (index, chunk) in chunks.iter().enumerate() {
+        let gas = chunk.gas.map(|gas| gas.to_string()).unwrap_or_else(|| "unknown".to_string());
+        println!("Chunk {} / {} -- planets {} -- estimated gas {}", index + 1, chunks.len(), planet_list(&chunk.planet_ids), gas);
+    }
+
+    if args.tx.dry_run {
+        let mut harvested = Resources::default();
+        for (planet_id, pending) in planets_for_address.iter().zip(client.pending_resources_of(&planets_for_address).await?) {
+            println!("Planet {} -- {} moved to the wallet", planet_id, pending);
+            harvested = harvested + pending;
+        }
+        let balances = client.balances(wallet_address).await?;
+        println!("Wallet {:?} -- {} -> {}", wallet_address, balances, balances + harvested);
+
+        for chunk in &chunks {
+            let simulation = client.simulate(wallet_address, &client.harvest_request(&chunk.planet_ids), fees).await?;
+            print_simulation(&simulation);
+        }
+        return Ok(());
+    }
+
+    let mut failure = None;
+    for (index, chunk) in chunks.iter().enumerate() {
+        let label = format!("Harvest chunk {} / {}", index + 1, chunks.len());
+        if let Err(err) = harvest_chunk(client, &chunk.planet_ids, fees, args.resubmit, &label).await {
+            println!("{} -- planets {} -- failed -- {}", label, planet_list(&chunk.planet_ids), err);
+            failure = Some(err);
+        }
+    }
+
+    match failure {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+/// Planets to harvest among the `owned` ones, printing every planet left out and why.
+///
+/// `--min-pending` is compared with each resource on its own : a planet is kept when at least one of its pending
+/// solar, metal or crystal reaches it, the three tokens having different values. `--only-if-worth`
+/// compares the value of the pending resources, from the prices of the profile, with the fee of a transaction
+/// harvesting the planet alone at the expected gas price (base fee of the next block plus priority fee, up to the
+/// max fee); a planet whose harvest cannot be estimated is left out.
+async fn select_planets(client: &NovaxClient, wallet_address: Address, owned: Vec<U256>, fees: &FeePolicy, prices: &ResourcePrices, args: &HarvestArgs) -> Result<Vec<U256>> {
+    let candidates = if args.planets.is_empty() {
+        owned
+    } else {
+        if let Some(planet_id) = args.planets.iter().find(|planet_id| !owned.contains(planet_id)) {
+            return Err(NovaxError::config(format!("planet {} is not owned by {:?}", planet_id, wallet_address)));
+        }
+        args.planets.clone()
+    };
+    if args.min_pending.is_none() && !args.only_if_worth {
+        return Ok(candidates);
+    }
+
+    let gas_price = if args.only_if_worth {
+        if prices.value_of(&Resources::default()).is_none() {
+            return Err(NovaxError::config("--only-if-worth needs the solar, metal and crystal prices of the profile, or --solar-price, --metal-price and --crystal-price"));
+        }
+        Some(fees.expected_price(client.web3()).await?)
+    } else {
+        None
+    };
+
+    let mut selected = Vec::new();
+    let pending_resources = client.pending_resources_of(&candidates).await?;
+    for (planet_id, pending) in candidates.into_iter().zip(pending_resources) {
+        if let Some(min_pending) = args.min_pending {
+            if Resource::ALL.iter().all(|resource| pending.get(*resource) < min_pending) {
+                println!("Planet {} -- skipped -- {} pending, every resource below the minimum of {}", planet_id, pending, format_decimals(min_pending));
+                continue;
+            }
+        }
+        if let Some(gas_price) = gas_price {
+            let value = prices.value_of(&pending).unwrap_or_default();
+            let gas = match client.estimate_harvest(wallet_address, &[planet_id]).await {
+                Ok(gas) => gas,
+                Err(web3::Error::Rpc(err)) => {
+                    println!("Planet {} -- skipped -- harvest gas estimation failed -- {}", planet_id, err.message);
+                    continue;
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let fee = to_decimals(gas.saturating_mul(gas_price));
+            if value < fee {
+                println!("Planet {} -- skipped -- {} pending worth {:.6} AVAX, less than the {:.6} AVAX fee", planet_id, pending, value, fee);
+                continue;
+            }
+        }
+        selected.push(planet_id);
+    }
+    Ok(selected)
+}
+
+/// Send one `harvestAll` transaction, again with a fresh gas estimate up to `resubmit` times when it reverts.
+async fn harvest_chunk(client: &NovaxClient, planet_ids: &[U256], fees: &FeePolicy, resubmit: u32, label: &str) -> Result<()> {
+    let mut attempt = 0;
+    loop {
+        match client.harvest(planet_ids, fees).await {
+            Ok(outcome) => {
+                print_receipt(label, &outcome.receipt, None);
+                return Ok(());
+            }
+            Err(NovaxError::Reverted { tx_hash, reason, receipt }) => {
+                print_receipt(label, &receipt, reason.as_deref());
+                if attempt >= resubmit {
+                    return Err(NovaxError::Reverted { tx_hash, reason, receipt });
+                }
+                attempt += 1;
+                println!("Sending the harvest again ({} / {})", attempt, resubmit);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn planet_list(planet_ids: &[U256]) -> String {
+    planet_ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(",")
+}
@@ -0,0 +1,42 @@
+use novax::error::{NovaxError, Result};
+use novax::keystore::{address_of, load_key, parse_private_key, read_passphrase, Passphrase};
+use crate::cli::{KeyArgs, KeystoreCommand};
+
+/// Run a `keystore` subcommand.
+pub fn keystore_command(command: &KeystoreCommand, key_args: &KeyArgs) -> Result<()> {
+    match command {
+        KeystoreCommand::Import { name } => {
+            let key = match key_args.source() {
+                Some(source) => load_key(&source, &key_args.passphrase())?,
+                None => parse_private_key(&rpassword::prompt_password("Private key (hex): ")?)?,
+            };
+            let password = new_passphrase(&key_args.passphrase())?;
+            let path = novax::keystore::import(&key, name.as_deref(), &password)?;
+            println!("Imported {:?} into {}", address_of(&key), path.display());
+        }
+        KeystoreCommand::New { name } => {
+            let password = new_passphrase(&key_args.passphrase())?;
+            let (address, path) = novax::keystore::generate(name.as_deref(), &password)?;
+            println!("Generated {:?} into {}", address, path.display());
+        }
+        KeystoreCommand::List => {
+            for entry in novax::keystore::list()? {
+                match entry.address {
+                    Some(address) => println!("{} -- {:?}", entry.name, address),
+                    None => println!("{} -- unknown address", entry.name),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn new_passphrase(passphrase: &Passphrase) -> Result<String> {
+    let password = read_passphrase(passphrase, "Passphrase of the new keystore: ")?;
+    if let Passphrase::Prompt = passphrase {
+        if read_passphrase(passphrase, "Repeat the passphrase: ")? != password {
+            return Err(NovaxError::keystore("the passphrases do not match"));
+        }
+    }
+    Ok(password)
+}
@@ -0,0 +1,79 @@
+use std::io::{self, BufRead, Write};
+use web3::ethabi::{Address, Token};
+use web3::ethabi::ethereum_types::U256;
+use novax::client::NovaxClient;
+use novax::fees::FeePolicy;
+use novax::error::{NovaxError, Result};
+use novax::tx::TxOutcome;
+use novax::utils::to_decimals;
+use crate::cli::MintPlanetArgs;
+use crate::commands::receipt::print_receipt;
+use crate::commands::simulation::print_simulation;
+
+/// Mint `--count` planets with `createPlanet`, and print the ids of the new planets.
+///
+/// The planet fee and the remaining supply are read first, then the total cost is shown and confirmed (unless
+/// `--yes`) before the transaction is sent. With `--dry-run`, the transaction is simulated without asking.
+pub async fn mint_planet(client: &NovaxClient, wallet_address: Address, args: &MintPlanetArgs, fees: &FeePolicy) -> Result<()> {
+    let planet_fee = client.planet_fee().await?;
+    let max_planets = client.max_planets().await?;
+    let remaining = max_planets.saturating_sub(client.minted_planets().await?);
+    let total = planet_fee.saturating_mul(U256::from(args.count));
+    println!("Planet fee {} AVAX -- {} / {} planets left to mint", to_decimals(planet_fee), remaining, max_planets);
+    println!("Minting {} planet(s) for {} AVAX (plus the transaction fee)", args.count, to_decimals(total));
+
+    if remaining < U256::from(args.count) {
+        return Err(NovaxError::config(format!("only {} planets are left to mint, {} requested", remaining, args.count)));
+    }
+    let available = client.avax_balance(wallet_address).await?;
+    if available < total {
+        return Err(NovaxError::InsufficientFunds { needed: total, available });
+    }
+
+    let request = client.mint_request(args.count, planet_fee);
+    if args.tx.dry_run {
+        print_simulation(&client.simulate(wallet_address, &request, fees).await?);
+        return Ok(());
+    }
+    if !args.yes && !confirm(&format!("Send {} AVAX to mint {} planet(s) ?", to_decimals(total), args.count))? {
+        println!("Aborted, nothing was sent");
+        return Ok(());
+    }
+
+    match client.mint_planets(args.count, planet_fee, fees).await {
+        Ok(outcome) => {
+            print_receipt("Mint tx", &outcome.receipt, None);
+            for planet_id in minted_planet_ids(client, &outcome, wallet_address) {
+                println!("Planet {} minted to {:?}", planet_id, wallet_address);
+            }
+            Ok(())
+        }
+        Err(NovaxError::Reverted { tx_hash, reason, receipt }) => {
+            print_receipt("Mint tx", &receipt, reason.as_deref());
+            Err(NovaxError::Reverted { tx_hash, reason, receipt })
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Ids of the planets minted to `owner` : the `Transfer` events of the planet contract coming from the zero address.
+fn minted_planet_ids(client: &NovaxClient, outcome: &TxOutcome, owner: Address) -> Vec<U256> {
+    outcome
+        .events
+        .iter()
+        .filter(|event| event.address == client.planet_contract().address() && event.name == "Transfer")
+        .filter_map(|event| match (event.param("from"), event.param("to"), event.param("tokenId")) {
+            (Some(Token::Address(from)), Some(Token::Address(to)), Some(Token::Uint(token_id))) if from.is_zero() && *to == owner => Some(*token_id),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Ask a yes / no question on the terminal, no being the answer by default.
+fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes"))
+}
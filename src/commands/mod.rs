@@ -0,0 +1,14 @@
+//! Implementation of the CLI commands on top of the `novax` library.
+
+pub mod build;
+pub mod fetch_info;
+pub mod harvest;
+pub mod keystore;
+pub mod mint_planet;
+pub mod planet;
+pub mod receipt;
+pub mod simulation;
+pub mod token;
+pub mod tx;
+pub mod upgrade;
+pub mod withdraw;
@@ -0,0 +1,69 @@
+use web3::ethabi::Address;
+use web3::ethabi::ethereum_types::U256;
+use novax::client::NovaxClient;
+use novax::fees::FeePolicy;
+use novax::error::{NovaxError, Result};
+use crate::cli::PlanetCommand;
+use crate::commands::receipt::send_requests;
+
+/// Transfer planets of the wallet, approve an address to transfer them, or list (and revoke) the approvals.
+pub async fn planet_command(client: &NovaxClient, wallet_address: Address, planets_for_address: Vec<U256>, command: &PlanetCommand, fees: &FeePolicy) -> Result<()> {
+    match command {
+        PlanetCommand::Transfer { to, planets, data, unchecked, tx } => {
+            ensure_owned(wallet_address, &planets_for_address, planets)?;
+            let requests: Vec<_> = planets
+                .iter()
+                .map(|planet_id| match unchecked {
+                    true => client.planet_transfer_request(wallet_address, *to, *planet_id),
+                    false => client.planet_safe_transfer_request(wallet_address, *to, *planet_id, data.as_deref()),
+                })
+                .collect();
+            send_requests(client, wallet_address, &requests, fees, tx.dry_run).await
+        }
+        PlanetCommand::Approve { spender, planets, all, tx } => {
+            let requests = if *all {
+                vec![client.planet_operator_request(*spender, true)]
+            } else {
+                ensure_owned(wallet_address, &planets_for_address, planets)?;
+                planets.iter().map(|planet_id| client.planet_approve_request(*spender, *planet_id)).collect()
+            };
+            send_requests(client, wallet_address, &requests, fees, tx.dry_run).await
+        }
+        PlanetCommand::Approvals { from_block, operators, revoke, tx } => {
+            let mut requests = Vec::new();
+            for planet_id in planets_for_address {
+                if let Some(approved) = client.planet_approval(planet_id).await? {
+                    println!("Planet {} -- may be transferred by {:?}", planet_id, approved);
+                    requests.push(client.planet_approve_request(Address::zero(), planet_id));
+                }
+            }
+
+            let mut approved_operators = client.planet_operators(wallet_address, *from_block).await?;
+            for operator in operators {
+                if !approved_operators.contains(operator) && client.is_planet_operator(wallet_address, *operator).await? {
+                    approved_operators.push(*operator);
+                }
+            }
+            for operator in &approved_operators {
+                println!("Operator {:?} -- may transfer every planet of {:?}", operator, wallet_address);
+                requests.push(client.planet_operator_request(*operator, false));
+            }
+
+            if requests.is_empty() {
+                println!("No address may transfer the planets of {:?}", wallet_address);
+                return Ok(());
+            }
+            match revoke {
+                true => send_requests(client, wallet_address, &requests, fees, tx.dry_run).await,
+                false => Ok(()),
+            }
+        }
+    }
+}
+
+fn ensure_owned(wallet_address: Address, planets_for_address: &[U256], planets: &[U256]) -> Result<()> {
+    match planets.iter().find(|planet_id| !planets_for_address.contains(planet_id)) {
+        Some(planet_id) => Err(NovaxError::config(format!("planet {} is not owned by {:?}", planet_id, wallet_address))),
+        None => Ok(()),
+    }
+}
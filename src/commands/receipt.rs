@@ -0,0 +1,53 @@
+use web3::ethabi::Address;
+use web3::types::TransactionReceipt;
+use novax::client::NovaxClient;
+use novax::error::{NovaxError, Result};
+use novax::fees::FeePolicy;
+use novax::tx::{receipt_fee, TxRequest};
+use novax::utils::to_decimals;
+use crate::commands::simulation::print_simulation;
+
+/// Print the outcome of a mined transaction, with the revert reason when it reverted.
+pub fn print_receipt(label: &str, receipt: &TransactionReceipt, revert_reason: Option<&str>) {
+    match (receipt.status.map(|status| status.as_u64()), revert_reason) {
+        (Some(0), Some(reason)) => println!("{} -- reverted -- {:?} -- {}", label, receipt.transaction_hash, reason),
+        (Some(0), None) => println!("{} -- reverted -- {:?}", label, receipt.transaction_hash),
+        _ => println!("{} -- success -- {:?}", label, receipt.transaction_hash),
+    }
+    println!("    block    : {}", receipt.block_number.unwrap_or_default());
+    println!("    gas used : {}", receipt.gas_used.unwrap_or_default());
+    match receipt_fee(receipt) {
+        Some(fee) => println!("    fee      : {} AVAX", to_decimals(fee)),
+        None => println!("    fee      : unknown, the node does not report the effective gas price"),
+    }
+}
+
+/// Send the requests back to back and print their receipts, or simulate them with `dry_run`. A failed transaction
+/// does not stop the next ones, the last failure is returned.
+pub async fn send_requests(client: &NovaxClient, wallet_address: Address, requests: &[TxRequest], fees: &FeePolicy, dry_run: bool) -> Result<()> {
+    if dry_run {
+        for request in requests {
+            print_simulation(&client.simulate(wallet_address, request, fees).await?);
+        }
+        return Ok(());
+    }
+
+    let mut failure = None;
+    for (request, outcome) in requests.iter().zip(client.send_batch(requests, fees).await?) {
+        match outcome {
+            Ok(outcome) => print_receipt(&request.label, &outcome.receipt, None),
+            Err(NovaxError::Reverted { tx_hash, reason, receipt }) => {
+                print_receipt(&request.label, &receipt, reason.as_deref());
+                failure = Some(NovaxError::Reverted { tx_hash, reason, receipt });
+            }
+            Err(err) => {
+                println!("{} -- failed -- {}", request.label, err);
+                failure = Some(err);
+            }
+        }
+    }
+    match failure {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,23 @@
+use novax::tx::{CallOutcome, Simulation};
+use novax::utils::to_decimals;
+
+/// Print what a simulated transaction would do.
+pub fn print_simulation(simulation: &Simulation) {
+    println!("Dry run -- {}", simulation.request.label);
+    println!("    from     : {:?}", simulation.from);
+    println!("    to       : {:?}", simulation.request.to);
+    println!("    calldata : 0x{}", hex::encode(&simulation.request.data.0));
+    match &simulation.call {
+        CallOutcome::Success(data) => println!("    eth_call : success (0x{})", hex::encode(&data.0)),
+        CallOutcome::Reverted(Some(reason)) => println!("    eth_call : reverted -- {}", reason),
+        CallOutcome::Reverted(None) => println!("    eth_call : reverted without a reason"),
+    }
+    match &simulation.gas {
+        Ok(gas) => println!("    gas      : {}", gas),
+        Err(err) => println!("    gas      : cannot be estimated -- {}", err),
+    }
+    println!("    fees     : {}", simulation.fees);
+    if let Some(max_fee) = simulation.max_fee() {
+        println!("    cost     : at most {} AVAX", to_decimals(max_fee));
+    }
+}
@@ -0,0 +1,58 @@
+use web3::ethabi::Address;
+use web3::ethabi::ethereum_types::U256;
+use novax::client::NovaxClient;
+use novax::contracts::{Resource, Resources};
+use novax::fees::FeePolicy;
+use novax::error::Result;
+use novax::utils::format_decimals;
+use crate::cli::TokenCommand;
+use crate::commands::receipt::send_requests;
+
+/// Transfer resource tokens of the wallet, set an allowance, or list (and revoke) the allowances of the wallet.
+pub async fn token_command(client: &NovaxClient, wallet_address: Address, command: &TokenCommand, fees: &FeePolicy) -> Result<()> {
+    match command {
+        TokenCommand::Transfer { resource, to, amount, tx } => {
+            client.ensure_resources(wallet_address, &Resources::only(*resource, *amount)).await?;
+            println!("Sending {} {} from {:?} to {:?}", format_decimals(*amount), resource, wallet_address, to);
+            send_requests(client, wallet_address, &[client.token_transfer_request(*resource, *to, *amount)], fees, tx.dry_run).await
+        }
+        TokenCommand::Approve { resource, spender, amount, increase, decrease, tx } => {
+            let current = client.token_allowance(*resource, wallet_address, *spender).await?;
+            let (request, wanted) = match (increase, decrease) {
+                (true, _) => (client.token_allowance_change_request(*resource, *spender, *amount, true), current.saturating_add(*amount)),
+                (_, true) => (client.token_allowance_change_request(*resource, *spender, *amount, false), current.saturating_sub(*amount)),
+                _ => (client.token_approve_request(*resource, *spender, *amount), *amount),
+            };
+            println!("{} allowance of {:?} -- {} -> {}", resource, spender, format_decimals(current), format_decimals(wanted));
+            send_requests(client, wallet_address, &[request], fees, tx.dry_run).await
+        }
+        TokenCommand::Allowances { from_block, spenders, revoke, tx } => {
+            let mut requests = Vec::new();
+            for resource in Resource::ALL {
+                let mut allowances = client.token_allowances(resource, wallet_address, *from_block).await?;
+                for spender in spenders {
+                    if allowances.iter().any(|(listed, _)| listed == spender) {
+                        continue;
+                    }
+                    let allowance = client.token_allowance(resource, wallet_address, *spender).await?;
+                    if !allowance.is_zero() {
+                        allowances.push((*spender, allowance));
+                    }
+                }
+                for (spender, allowance) in allowances {
+                    println!("{} -- {:?} may spend {} tokens of {:?}", resource, spender, format_decimals(allowance), wallet_address);
+                    requests.push(client.token_approve_request(resource, spender, U256::zero()));
+                }
+            }
+
+            if requests.is_empty() {
+                println!("No allowance granted by {:?}", wallet_address);
+                return Ok(());
+            }
+            match revoke {
+                true => send_requests(client, wallet_address, &requests, fees, tx.dry_run).await,
+                false => Ok(()),
+            }
+        }
+    }
+}
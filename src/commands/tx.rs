@@ -0,0 +1,25 @@
+use web3::ethabi::ethereum_types::U256;
+use novax::client::NovaxClient;
+use novax::error::Result;
+use novax::fees::FeePolicy;
+use crate::cli::TxCommand;
+use crate::commands::receipt::print_receipt;
+
+/// Speed up or cancel a pending transaction of the wallet, and wait for the replacement to be mined.
+pub async fn tx_command(client: &NovaxClient, command: &TxCommand, fees: &FeePolicy) -> Result<()> {
+    let sender = client.sender()?;
+    let (replacement, dry_run) = match command {
+        TxCommand::Speedup { hash, tx } => (sender.speed_up(*hash).await?, tx.dry_run),
+        TxCommand::Cancel { nonce, tx } => (sender.cancel(U256::from(*nonce), fees).await?, tx.dry_run),
+    };
+    println!("{} -- nonce {} -- gas {} -- {}", replacement.request.label, replacement.nonce, replacement.gas, replacement.fees);
+    if dry_run {
+        println!("Dry run -- nothing was sent");
+        return Ok(());
+    }
+
+    let pending = sender.send_replacement(&replacement).await?;
+    let outcome = sender.confirm(pending, fees).await?;
+    print_receipt(&replacement.request.label, &outcome.receipt, None);
+    Ok(())
+}
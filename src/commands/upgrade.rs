@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use web3::ethabi::{Address, Token};
+use web3::futures::try_join;
+use web3::ethabi::ethereum_types::U256;
+use novax::client::NovaxClient;
+use novax::fees::FeePolicy;
+use novax::error::{NovaxError, Result};
+use novax::contracts::Structure;
+use crate::commands::simulation::print_simulation;
+
+/// Level up by one level the `structures` of every planet that are below `threshold`, as long as the wallet holds
+/// enough resources.
+///
+/// The affordable upgrades are planned against a local copy of the wallet balances, then submitted back to back and
+/// followed until they are mined. Upgrades the wallet cannot afford are skipped; once every planet has been processed
+/// the last of them is reported as an [`NovaxError::InsufficientResources`] error, unless an upgrade transaction
+/// failed. With `dry_run` the planned upgrades are simulated instead of being sent.
+pub async fn upgrade_buildings(client: &NovaxClient, wallet_address: Address, planets_for_address: Vec<U256>, structures: &[Structure], threshold: u32, fees: &FeePolicy, dry_run: bool) -> Result<()> {
+    let (mut balances, metadata) = try_join!(client.balances(wallet_address), client.planets_metadata(&planets_for_address))?;
+    // Planets without metadata have unknown levels, they are left out.
+    let (planets_for_address, planets): (Vec<_>, Vec<_>) = planets_for_address.into_iter().zip(metadata).filter_map(|(planet_id, planet)| Some((planet_id, planet?))).unzip();
+    let structures: Vec<_> = Structure::ALL.iter().copied().filter(|structure| structures.contains(structure)).collect();
+
+    // Planets with the same structure level share the same cost : every cost needed is read once, in one batch.
+    let mut needed = Vec::new();
+    for planet in &planets {
+        for structure in &structures {
+            let level = planet.structure_level(*structure);
+            if level < threshold && !needed.contains(&(*structure, level + 1)) {
+                needed.push((*structure, level + 1));
+            }
+        }
+    }
+    let costs: HashMap<_, _> = needed.iter().copied().zip(client.upgrade_costs(&needed).await?).collect();
+
+    let mut insufficient_resources = None;
+    let mut upgrades = Vec::new();
+    for (planet_id, planet) in planets_for_address.into_iter().zip(planets) {
+        for structure in structures.iter().copied() {
+            let level = planet.structure_level(structure);
+            if level >= threshold {
+                println!("{} on this planet {} is already at the wanted level", structure, planet_id);
+                continue;
+            }
+
+            let next_upgrade_level = level + 1;
+            let upgrade_cost = costs[&(structure, next_upgrade_level)];
+            println!("Cost for upgrading {} for planet {} -- {:?}", structure, planet_id, upgrade_cost);
+            if !balances.covers(&upgrade_cost) {
+                let err = NovaxError::InsufficientResources { needed: Box::new(upgrade_cost), available: Box::new(balances) };
+                println!("We don't have enough resources to perform this upgrade -- {}", err);
+                insufficient_resources = Some(err);
+                continue;
+            }
+
+            balances = balances.saturating_sub(&upgrade_cost);
+            println!("Level up {} from level {} to {} on planet {} -- {} spent, {} left", structure, level, next_upgrade_level, planet_id, upgrade_cost, balances);
+            upgrades.push((structure, planet_id));
+        }
+    }
+
+    let requests: Vec<_> = upgrades.iter().map(|(structure, planet_id)| client.upgrade_request(*structure, *planet_id)).collect();
+    let mut failure = None;
+    if dry_run {
+        for request in &requests {
+            print_simulation(&client.simulate(wallet_address, request, fees).await?);
+        }
+    } else {
+        for ((structure, planet_id), outcome) in upgrades.iter().zip(client.send_batch(&requests, fees).await?) {
+            match outcome {
+                Ok(outcome) => {
+                    for event in outcome.events.iter().filter(|event| event.name == "LevelUp") {
+                        if let (Some(Token::Uint(level)), Some(Token::Uint(planet_no))) = (event.param("_level"), event.param("_planetNo")) {
+                            println!("{} of planet {} is now level {}", structure, planet_no, level);
+                        }
+                    }
+                }
+                Err(err) => {
+                    println!("Level up of {} on planet {} failed -- {}", structure, planet_id, err);
+                    failure = Some(err);
+                }
+            }
+        }
+    }
+
+    match failure.or(insufficient_resources) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,45 @@
+use web3::ethabi::Address;
+use web3::ethabi::ethereum_types::U256;
+use novax::client::NovaxClient;
+use novax::contracts::Resources;
+use novax::fees::FeePolicy;
+use novax::error::{NovaxError, Result};
+use crate::cli::WithdrawArgs;
+use crate::commands::receipt::print_receipt;
+use crate::commands::simulation::print_simulation;
+
+/// Withdraw an amount of one resource from a planet of the wallet with `withdrawResource`, and print the receipt.
+///
+/// The amount is checked against the pending resources of the planet (`getResourceAmount`) before anything is sent.
+/// With `--dry-run`, print the wallet balances before and after and the simulated transaction instead.
+pub async fn withdraw(client: &NovaxClient, wallet_address: Address, planets_for_address: Vec<U256>, args: &WithdrawArgs, fees: &FeePolicy) -> Result<()> {
+    if !planets_for_address.contains(&args.planet) {
+        return Err(NovaxError::config(format!("planet {} is not owned by {:?}", args.planet, wallet_address)));
+    }
+
+    let withdrawn = Resources::only(args.resource, args.amount);
+    let pending = client.pending_resources(args.planet).await?;
+    println!("Planet {} -- {} pending -- withdrawing {}", args.planet, pending, withdrawn);
+    client.ensure_pending(args.resource, args.amount, args.planet).await?;
+
+    if args.tx.dry_run {
+        let balances = client.balances(wallet_address).await?;
+        println!("Wallet {:?} -- {} -> {}", wallet_address, balances, balances + withdrawn);
+        let simulation = client.simulate(wallet_address, &client.withdraw_request(args.resource, args.amount, args.planet), fees).await?;
+        print_simulation(&simulation);
+        return Ok(());
+    }
+
+    match client.withdraw(args.resource, args.amount, args.planet, fees).await {
+        Ok(outcome) => {
+            print_receipt("Withdraw tx", &outcome.receipt, None);
+            println!("Wallet {:?} -- {}", wallet_address, client.balances(wallet_address).await?);
+            Ok(())
+        }
+        Err(NovaxError::Reverted { tx_hash, reason, receipt }) => {
+            print_receipt("Withdraw tx", &receipt, reason.as_deref());
+            Err(NovaxError::Reverted { tx_hash, reason, receipt })
+        }
+        Err(err) => Err(err),
+    }
+}
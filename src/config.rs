@@ -0,0 +1,494 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer};
+use web3::ethabi::Address;
+use web3::ethabi::ethereum_types::U256;
+use crate::contracts::Resources;
+use crate::error::{NovaxError, Result};
+use crate::gas_oracle::{parse_gas_amount, GasStrategy};
+use crate::metadata::MAX_METADATA_RETRIES;
+use crate::multicall::MULTICALL3_ADDRESS;
+use crate::production::ProductionTable;
+use crate::tx::StuckPolicy;
+use crate::utils::to_decimals;
+
+pub const DEFAULT_PROFILE: &str = "mainnet";
+
+/// Content of the TOML configuration file.
+///
+/// ```toml
+/// default_profile = "local"
+///
+/// [profiles.local]
+/// inherits = "mainnet"
+/// rpc_url = "ws://127.0.0.1:8545"
+///
+/// [profiles.local.contracts]
+/// game = "0x2aa2a9ef24a209f47f42Cb97Bd19D881e33F3956"
+/// ```
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigFile {
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ProfileOverrides>,
+}
+
+/// Keys of a profile, every one of them being optional so a profile only has to set what differs.
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileOverrides {
+    /// Profile the missing keys are taken from
+    pub inherits: Option<String>,
+    pub rpc_url: Option<String>,
+    pub chain_id: Option<u64>,
+    /// Gas price of legacy transactions, in wei or with a unit (e.g. `"25gwei"`). Type-2 (EIP-1559) transactions are
+    /// sent when not set
+    #[serde(default, deserialize_with = "gas_amount")]
+    pub gas_price: Option<U256>,
+    /// Gas oracle strategy of type-2 transactions : `standard`, `fast` or `max=AMOUNT` (e.g. `max=50gwei`)
+    pub gas_strategy: Option<String>,
+    /// Ceiling of the `maxFeePerGas` of type-2 transactions, in wei or with a unit : the tool waits while the base fee
+    /// is above it
+    #[serde(default, deserialize_with = "gas_amount")]
+    pub max_fee: Option<U256>,
+    /// How long to wait for the base fee to drop below `max_fee`, in seconds
+    pub max_fee_wait: Option<u64>,
+    /// `maxPriorityFeePerGas` of type-2 transactions, in wei or with a unit (median of the recent blocks when not set)
+    #[serde(default, deserialize_with = "gas_amount")]
+    pub priority_fee: Option<U256>,
+    /// Number of blocks to wait for after a transaction is mined
+    pub confirmations: Option<u64>,
+    /// How long to wait for a transaction to be mined and confirmed, in seconds
+    pub receipt_timeout: Option<u64>,
+    /// What to do with a transaction still pending after `stuck_timeout` : `wait`, `speed-up` or `cancel`
+    pub on_stuck: Option<String>,
+    /// Seconds after which a pending transaction is considered stuck
+    pub stuck_timeout: Option<u64>,
+    /// Fee increase of the replacement transactions, in percent
+    pub fee_bump: Option<u64>,
+    /// How many planets are queried at the same time
+    pub concurrency: Option<usize>,
+    /// Timeout of each request to the metadata server, in seconds
+    pub metadata_timeout: Option<u64>,
+    /// How many times a failed request to the metadata server is retried
+    pub metadata_retries: Option<u32>,
+    #[serde(default)]
+    pub contracts: ContractOverrides,
+    #[serde(default)]
+    pub prices: ResourcePrices,
+    #[serde(default)]
+    pub production: ProductionOverrides,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ContractOverrides {
+    pub planet: Option<Address>,
+    pub game: Option<Address>,
+    pub metal: Option<Address>,
+    pub solar: Option<Address>,
+    pub crystal: Option<Address>,
+    /// Multicall3 contract batching the reads
+    pub multicall: Option<Address>,
+}
+
+/// Value of one resource token, in AVAX, used to decide whether a harvest is worth its fee.
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ResourcePrices {
+    pub solar: Option<f64>,
+    pub metal: Option<f64>,
+    pub crystal: Option<f64>,
+}
+
+/// Production rates of the structures, in tokens per second, by level (see [`ProductionTable`]).
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ProductionOverrides {
+    pub solar: Option<Vec<f64>>,
+    pub metal: Option<Vec<f64>>,
+    pub crystal: Option<Vec<f64>>,
+}
+
+/// Fully resolved profile, consumed by the rest of the tool.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub rpc_url: String,
+    pub chain_id: u64,
+    pub gas_price: Option<U256>,
+    pub gas_strategy: Option<GasStrategy>,
+    pub max_fee: Option<U256>,
+    pub max_fee_wait: Option<u64>,
+    pub priority_fee: Option<U256>,
+    pub confirmations: Option<u64>,
+    pub receipt_timeout: Option<u64>,
+    pub on_stuck: Option<StuckPolicy>,
+    pub stuck_timeout: Option<u64>,
+    pub fee_bump: Option<u64>,
+    pub concurrency: Option<usize>,
+    pub metadata_timeout: Option<u64>,
+    pub metadata_retries: Option<u32>,
+    pub contracts: Contracts,
+    pub prices: ResourcePrices,
+    pub production: ProductionTable,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Contracts {
+    pub planet: Address,
+    pub game: Address,
+    pub metal: Address,
+    pub solar: Address,
+    pub crystal: Address,
+    /// Reads are batched in JSON-RPC batch requests instead when there is none
+    pub multicall: Option<Address>,
+}
+
+impl ResourcePrices {
+    /// Value of `resources` in AVAX, `None` unless the three prices are known.
+    pub fn value_of(&self, resources: &Resources) -> Option<f64> {
+        Some(to_decimals(resources.solar) * self.solar? + to_decimals(resources.metal) * self.metal? + to_decimals(resources.crystal) * self.crystal?)
+    }
+}
+
+impl ProfileOverrides {
+    /// Set every key of `self` that is also set in `other`.
+    pub fn merge(&mut self, other: &ProfileOverrides) {
+        if other.rpc_url.is_some() { self.rpc_url = other.rpc_url.clone(); }
+        if other.chain_id.is_some() { self.chain_id = other.chain_id; }
+        if other.gas_price.is_some() { self.gas_price = other.gas_price; }
+        if other.gas_strategy.is_some() { self.gas_strategy = other.gas_strategy.clone(); }
+        if other.max_fee.is_some() { self.max_fee = other.max_fee; }
+        if other.max_fee_wait.is_some() { self.max_fee_wait = other.max_fee_wait; }
+        if other.priority_fee.is_some() { self.priority_fee = other.priority_fee; }
+        if other.confirmations.is_some() { self.confirmations = other.confirmations; }
+        if other.receipt_timeout.is_some() { self.receipt_timeout = other.receipt_timeout; }
+        if other.on_stuck.is_some() { self.on_stuck = other.on_stuck.clone(); }
+        if other.stuck_timeout.is_some() { self.stuck_timeout = other.stuck_timeout; }
+        if other.fee_bump.is_some() { self.fee_bump = other.fee_bump; }
+        if other.concurrency.is_some() { self.concurrency = other.concurrency; }
+        if other.metadata_timeout.is_some() { self.metadata_timeout = other.metadata_timeout; }
+        if other.metadata_retries.is_some() { self.metadata_retries = other.metadata_retries; }
+        if other.contracts.planet.is_some() { self.contracts.planet = other.contracts.planet; }
+        if other.contracts.game.is_some() { self.contracts.game = other.contracts.game; }
+        if other.contracts.metal.is_some() { self.contracts.metal = other.contracts.metal; }
+        if other.contracts.solar.is_some() { self.contracts.solar = other.contracts.solar; }
+        if other.contracts.crystal.is_some() { self.contracts.crystal = other.contracts.crystal; }
+        if other.contracts.multicall.is_some() { self.contracts.multicall = other.contracts.multicall; }
+        if other.prices.solar.is_some() { self.prices.solar = other.prices.solar; }
+        if other.prices.metal.is_some() { self.prices.metal = other.prices.metal; }
+        if other.prices.crystal.is_some() { self.prices.crystal = other.prices.crystal; }
+        if other.production.solar.is_some() { self.production.solar = other.production.solar.clone(); }
+        if other.production.metal.is_some() { self.production.metal = other.production.metal.clone(); }
+        if other.production.crystal.is_some() { self.production.crystal = other.production.crystal.clone(); }
+    }
+}
+
+/// Default location of the configuration file, `~/.config/novaxcli/config.toml` on linux.
+pub fn default_config_path() -> PathBuf {
+    dirs::config_dir().unwrap_or_else(|| PathBuf::from(".")).join("novaxcli").join("config.toml")
+}
+
+/// Read the configuration file. A missing file is only an error when its path was explicitly given.
+pub fn load_config_file(path: Option<&Path>) -> Result<ConfigFile> {
+    let (path, explicit) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => (default_config_path(), false),
+    };
+    if !explicit && !path.exists() {
+        return Ok(ConfigFile::default());
+    }
+    let content = fs::read_to_string(&path).map_err(|err| NovaxError::config(format!("cannot read config file {} : {}", path.display(), err)))?;
+    let config = toml::from_str(&content).map_err(|err| NovaxError::config(format!("invalid config file {} : {}", path.display(), err)))?;
+    Ok(config)
+}
+
+/// Profiles available without any configuration file.
+pub fn builtin_profile(name: &str) -> Option<ProfileOverrides> {
+    let mainnet = ProfileOverrides {
+        inherits: None,
+        rpc_url: Some("wss://api.avax.network/ext/bc/C/ws".to_string()),
+        chain_id: Some(43114),
+        gas_price: None,
+        gas_strategy: None,
+        max_fee: None,
+        max_fee_wait: None,
+        priority_fee: None,
+        confirmations: None,
+        receipt_timeout: None,
+        on_stuck: None,
+        stuck_timeout: None,
+        fee_bump: None,
+        concurrency: None,
+        metadata_timeout: None,
+        metadata_retries: None,
+        contracts: ContractOverrides {
+            planet: Some(address("0x0C3b29321611736341609022C23E981AC56E7f96")),
+            game: Some(address("0x2aa2a9ef24a209f47f42Cb97Bd19D881e33F3956")),
+            metal: Some(address("0x4C1057455747e3eE5871D374FdD77A304cE10989")),
+            solar: Some(address("0xE6eE049183B474ecf7704da3F6F555a1dCAF240F")),
+            crystal: Some(address("0x70b4aE8eb7bd572Fc0eb244Cd8021066b3Ce7EE4")),
+            multicall: Some(address(MULTICALL3_ADDRESS)),
+        },
+        prices: ResourcePrices::default(),
+        production: ProductionOverrides::default(),
+    };
+
+    match name {
+        "mainnet" => Some(mainnet),
+        // NovaX is not deployed on Fuji, the contract addresses have to come from the config file.
+        "fuji" => Some(ProfileOverrides {
+            rpc_url: Some("wss://api.avax-test.network/ext/bc/C/ws".to_string()),
+            chain_id: Some(43113),
+            ..ProfileOverrides::default()
+        }),
+        // A local node forking mainnet (e.g. `anvil --fork-url https://api.avax.network/ext/bc/C/rpc`).
+        "local" => Some(ProfileOverrides {
+            rpc_url: Some("ws://127.0.0.1:8545".to_string()),
+            ..mainnet
+        }),
+        _ => None,
+    }
+}
+
+/// Resolve the profile `name` (or the default one) : built-in values, then the config file, then the CLI overrides.
+pub fn resolve_profile(config: &ConfigFile, name: Option<&str>, cli_overrides: &ProfileOverrides) -> Result<Profile> {
+    let name = name
+        .or(config.default_profile.as_deref())
+        .unwrap_or(DEFAULT_PROFILE)
+        .to_string();
+
+    let mut overrides = profile_overrides(config, &name, &mut Vec::new())?;
+    overrides.merge(cli_overrides);
+
+    let default_production = ProductionTable::default();
+    let production = ProductionTable {
+        solar: overrides.production.solar.unwrap_or(default_production.solar),
+        metal: overrides.production.metal.unwrap_or(default_production.metal),
+        crystal: overrides.production.crystal.unwrap_or(default_production.crystal),
+    };
+    production.validate().map_err(|err| NovaxError::config(format!("invalid production of profile {} : {}", name, err)))?;
+
+    let missing = |key: &str| NovaxError::config(format!("profile {} has no value for `{}`, set it in the config file or on the command line", name, key));
+    Ok(Profile {
+        rpc_url: overrides.rpc_url.ok_or_else(|| missing("rpc_url"))?,
+        chain_id: overrides.chain_id.ok_or_else(|| missing("chain_id"))?,
+        gas_price: overrides.gas_price,
+        gas_strategy: overrides.gas_strategy.as_deref()
+            .map(|strategy| strategy.parse().map_err(|err| NovaxError::config(format!("invalid gas_strategy of profile {} : {}", name, err))))
+            .transpose()?,
+        max_fee: overrides.max_fee,
+        max_fee_wait: overrides.max_fee_wait,
+        priority_fee: overrides.priority_fee,
+        confirmations: overrides.confirmations,
+        receipt_timeout: overrides.receipt_timeout,
+        on_stuck: overrides.on_stuck.as_deref()
+            .map(|policy| policy.parse().map_err(|err| NovaxError::config(format!("invalid on_stuck of profile {} : {}", name, err))))
+            .transpose()?,
+        stuck_timeout: overrides.stuck_timeout,
+        fee_bump: overrides.fee_bump,
+        concurrency: match overrides.concurrency {
+            Some(0) => return Err(NovaxError::config(format!("invalid concurrency of profile {} : at least one planet has to be queried at a time", name))),
+            concurrency => concurrency,
+        },
+        metadata_timeout: match overrides.metadata_timeout {
+            Some(0) => return Err(NovaxError::config(format!("invalid metadata_timeout of profile {} : it has to be at least one second", name))),
+            timeout => timeout,
+        },
+        metadata_retries: match overrides.metadata_retries {
+            Some(retries) if retries > MAX_METADATA_RETRIES => {
+                return Err(NovaxError::config(format!("invalid metadata_retries of profile {} : at most {} retries", name, MAX_METADATA_RETRIES)));
+            }
+            retries => retries,
+        },
+        contracts: Contracts {
+            planet: overrides.contracts.planet.ok_or_else(|| missing("contracts.planet"))?,
+            game: overrides.contracts.game.ok_or_else(|| missing("contracts.game"))?,
+            metal: overrides.contracts.metal.ok_or_else(|| missing("contracts.metal"))?,
+            solar: overrides.contracts.solar.ok_or_else(|| missing("contracts.solar"))?,
+            crystal: overrides.contracts.crystal.ok_or_else(|| missing("contracts.crystal"))?,
+            multicall: overrides.contracts.multicall,
+        },
+        prices: overrides.prices,
+        production,
+        name,
+    })
+}
+
+fn profile_overrides(config: &ConfigFile, name: &str, visited: &mut Vec<String>) -> Result<ProfileOverrides> {
+    if let Some(start) = visited.iter().position(|visited| visited == name) {
+        let cycle: Vec<&str> = visited[start..].iter().map(String::as_str).chain([name]).collect();
+        return Err(NovaxError::config(format!("profile inheritance cycle : {}", cycle.join(" -> "))));
+    }
+    visited.push(name.to_string());
+
+    let file_profile = config.profiles.get(name);
+    let mut overrides = match file_profile.and_then(|profile| profile.inherits.as_deref()) {
+        Some(parent) => profile_overrides(config, parent, visited)?,
+        None => match builtin_profile(name) {
+            Some(builtin) => builtin,
+            None if file_profile.is_some() => ProfileOverrides::default(),
+            None => return Err(NovaxError::config(format!("unknown profile {}", name))),
+        },
+    };
+    if let Some(file_profile) = file_profile {
+        overrides.merge(file_profile);
+    }
+    Ok(overrides)
+}
+
+/// A price per gas, as an integer number of wei or as a string with a unit (e.g. `"25gwei"`), see [`parse_gas_amount`].
+fn gas_amount<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<U256>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum GasAmount {
+        Wei(u64),
+        Text(String),
+    }
+    match GasAmount::deserialize(deserializer)? {
+        GasAmount::Wei(wei) => Ok(Some(U256::from(wei))),
+        GasAmount::Text(text) => parse_gas_amount(&text).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+fn address(value: &str) -> Address {
+    Address::from_str(value).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(toml: &str) -> ConfigFile {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn config_error(result: Result<Profile>) -> String {
+        match result {
+            Err(NovaxError::Config(message)) => message,
+            other => panic!("expected a config error, got {:?}", other.map(|profile| profile.name)),
+        }
+    }
+
+    #[test]
+    fn builtin_mainnet_is_the_default() {
+        let profile = resolve_profile(&ConfigFile::default(), None, &ProfileOverrides::default()).unwrap();
+        assert_eq!(profile.name, "mainnet");
+        assert_eq!(profile.chain_id, 43114);
+        assert_eq!(profile.contracts.game, address("0x2aa2a9ef24a209f47f42Cb97Bd19D881e33F3956"));
+        assert_eq!(profile.contracts.multicall, Some(address(MULTICALL3_ADDRESS)));
+        assert_eq!(profile.production, ProductionTable::default());
+    }
+
+    #[test]
+    fn file_profile_is_merged_over_the_builtin_one() {
+        let config = parse(r#"
+            [profiles.mainnet]
+            rpc_url = "wss://example.org/ws"
+            concurrency = 4
+        "#);
+        let profile = resolve_profile(&config, Some("mainnet"), &ProfileOverrides::default()).unwrap();
+        assert_eq!(profile.rpc_url, "wss://example.org/ws");
+        assert_eq!(profile.concurrency, Some(4));
+        assert_eq!(profile.chain_id, 43114);
+    }
+
+    #[test]
+    fn inherits_chains_are_followed() {
+        let config = parse(r#"
+            default_profile = "bot"
+
+            [profiles.fork]
+            inherits = "local"
+            confirmations = 2
+
+            [profiles.fork.contracts]
+            game = "0x0000000000000000000000000000000000000001"
+
+            [profiles.bot]
+            inherits = "fork"
+            confirmations = 3
+            fee_bump = 25
+        "#);
+        let profile = resolve_profile(&config, None, &ProfileOverrides::default()).unwrap();
+        assert_eq!(profile.name, "bot");
+        // From the builtin `local`, itself mainnet with a local node.
+        assert_eq!(profile.rpc_url, "ws://127.0.0.1:8545");
+        assert_eq!(profile.contracts.planet, address("0x0C3b29321611736341609022C23E981AC56E7f96"));
+        // From `fork`, and `bot` over it.
+        assert_eq!(profile.contracts.game, address("0x0000000000000000000000000000000000000001"));
+        assert_eq!(profile.confirmations, Some(3));
+        assert_eq!(profile.fee_bump, Some(25));
+    }
+
+    #[test]
+    fn cli_overrides_take_precedence() {
+        let config = parse(r#"
+            [profiles.mainnet]
+            rpc_url = "wss://example.org/ws"
+            chain_id = 1
+        "#);
+        let cli = ProfileOverrides { rpc_url: Some("ws://127.0.0.1:9650".to_string()), ..ProfileOverrides::default() };
+        let profile = resolve_profile(&config, None, &cli).unwrap();
+        assert_eq!(profile.rpc_url, "ws://127.0.0.1:9650");
+        assert_eq!(profile.chain_id, 1);
+    }
+
+    #[test]
+    fn inheritance_cycles_are_rejected() {
+        let config = parse(r#"
+            [profiles.a]
+            inherits = "b"
+
+            [profiles.b]
+            inherits = "c"
+
+            [profiles.c]
+            inherits = "a"
+        "#);
+        assert_eq!(config_error(resolve_profile(&config, Some("a"), &ProfileOverrides::default())), "profile inheritance cycle : a -> b -> c -> a");
+
+        let config = parse(r#"
+            [profiles.loop]
+            inherits = "loop"
+        "#);
+        assert_eq!(config_error(resolve_profile(&config, Some("loop"), &ProfileOverrides::default())), "profile inheritance cycle : loop -> loop");
+    }
+
+    #[test]
+    fn fee_keys_accept_wei_and_units() {
+        let config = parse(r#"
+            [profiles.mainnet]
+            gas_price = 25000000000
+            max_fee = "100gwei"
+            priority_fee = "2 gwei"
+        "#);
+        let profile = resolve_profile(&config, None, &ProfileOverrides::default()).unwrap();
+        assert_eq!(profile.gas_price, Some(U256::from(25_000_000_000u64)));
+        assert_eq!(profile.max_fee, Some(U256::from(100_000_000_000u64)));
+        assert_eq!(profile.priority_fee, Some(U256::from(2_000_000_000u64)));
+
+        for value in ["\"25 lightyears\"", "\"\"", "-1", "true"] {
+            assert!(toml::from_str::<ConfigFile>(&format!("[profiles.mainnet]\nmax_fee = {}", value)).is_err(), "{} was accepted", value);
+        }
+    }
+
+    #[test]
+    fn unknown_profiles_and_missing_keys_are_rejected() {
+        assert_eq!(config_error(resolve_profile(&ConfigFile::default(), Some("nope"), &ProfileOverrides::default())), "unknown profile nope");
+
+        let config = parse(r#"
+            [profiles.broken]
+            inherits = "nope"
+        "#);
+        assert_eq!(config_error(resolve_profile(&config, Some("broken"), &ProfileOverrides::default())), "unknown profile nope");
+
+        // Fuji has no NovaX contracts.
+        let message = config_error(resolve_profile(&ConfigFile::default(), Some("fuji"), &ProfileOverrides::default()));
+        assert!(message.contains("has no value for `contracts.planet`"), "{}", message);
+    }
+}
@@ -0,0 +1,63 @@
+use web3::api::Eth;
+use web3::contract::{Contract, Options};
+use web3::ethabi::{self, Address, Token};
+use web3::ethabi::ethereum_types::U256;
+use web3::Transport;
+use web3::transports::WebSocket;
+use web3::types::Bytes;
+use crate::multicall::ContractRead;
+use super::{encode_call, read, ERC20_ABI};
+
+/// A NovaX resource token (metal, solar or crystal), which is a plain ERC20.
+#[derive(Debug, Clone)]
+pub struct Erc20<T: Transport = WebSocket> {
+    contract: Contract<T>,
+}
+
+impl<T: Transport> Erc20<T> {
+    pub fn new(eth: Eth<T>, address: Address) -> Self {
+        Erc20 { contract: Contract::from_json(eth, address, ERC20_ABI).expect("embedded ERC20 ABI is valid") }
+    }
+
+    pub fn address(&self) -> Address {
+        self.contract.address()
+    }
+
+    pub fn abi(&self) -> &ethabi::Contract {
+        self.contract.abi()
+    }
+
+    pub async fn balance_of(&self, account: Address) -> web3::contract::Result<U256> {
+        self.contract.query("balanceOf", account, None, Options::default(), None).await
+    }
+
+    /// `balanceOf(account)`, to be batched.
+    pub fn balance_of_read(&self, account: Address) -> ContractRead {
+        read(&self.contract, "balanceOf", &[Token::Address(account)])
+    }
+
+    /// Amount `spender` may still transfer from the tokens of `owner`.
+    pub async fn allowance(&self, owner: Address, spender: Address) -> web3::contract::Result<U256> {
+        self.contract.query("allowance", (owner, spender), None, Options::default(), None).await
+    }
+
+    /// Calldata of `transfer(recipient, amount)`.
+    pub fn transfer(&self, recipient: Address, amount: U256) -> Bytes {
+        encode_call(&self.contract, "transfer", &[Token::Address(recipient), Token::Uint(amount)])
+    }
+
+    /// Calldata of `approve(spender, amount)`, zero revoking the allowance.
+    pub fn approve(&self, spender: Address, amount: U256) -> Bytes {
+        encode_call(&self.contract, "approve", &[Token::Address(spender), Token::Uint(amount)])
+    }
+
+    /// Calldata of `increaseAllowance(spender, addedValue)`.
+    pub fn increase_allowance(&self, spender: Address, added_value: U256) -> Bytes {
+        encode_call(&self.contract, "increaseAllowance", &[Token::Address(spender), Token::Uint(added_value)])
+    }
+
+    /// Calldata of `decreaseAllowance(spender, subtractedValue)`.
+    pub fn decrease_allowance(&self, spender: Address, subtracted_value: U256) -> Bytes {
+        encode_call(&self.contract, "decreaseAllowance", &[Token::Address(spender), Token::Uint(subtracted_value)])
+    }
+}
@@ -0,0 +1,262 @@
+use std::fmt;
+use std::ops::Add;
+use std::str::FromStr;
+use web3::api::Eth;
+use web3::contract::{Contract, Options};
+use web3::ethabi::{self, Address, Token};
+use web3::ethabi::ethereum_types::U256;
+use web3::Transport;
+use web3::transports::WebSocket;
+use web3::types::Bytes;
+use crate::utils::format_decimals;
+use crate::multicall::ContractRead;
+use super::{encode_call, read, NOVAX_GAME_ABI};
+
+/// A structure of a planet, identified on chain by a one letter id.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Structure {
+    /// Solar panel
+    Solar,
+    /// Metal mine
+    Mine,
+    /// Crystal laboratory
+    Crystal,
+}
+
+/// A resource produced by the planets. The discriminant is the `resourceIndex` of the game contract.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Resource {
+    Solar = 0,
+    Metal = 1,
+    Crystal = 2,
+}
+
+impl Structure {
+    pub const ALL: [Structure; 3] = [Structure::Solar, Structure::Mine, Structure::Crystal];
+
+    /// Id of the structure in the game contract.
+    pub fn id(self) -> &'static str {
+        match self {
+            Structure::Solar => "s",
+            Structure::Mine => "m",
+            Structure::Crystal => "c",
+        }
+    }
+
+    /// Resource produced by the structure.
+    pub fn resource(self) -> Resource {
+        match self {
+            Structure::Solar => Resource::Solar,
+            Structure::Mine => Resource::Metal,
+            Structure::Crystal => Resource::Crystal,
+        }
+    }
+
+    /// Name of the structure on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Structure::Solar => "solar",
+            Structure::Mine => "mine",
+            Structure::Crystal => "crystal",
+        }
+    }
+}
+
+impl fmt::Display for Structure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Structure::Solar => "solar panel",
+            Structure::Mine => "metal mine",
+            Structure::Crystal => "crystal laboratory",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for Structure {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Structure::ALL
+            .iter()
+            .copied()
+            .find(|structure| structure.name().eq_ignore_ascii_case(value) || structure.id() == value)
+            .ok_or_else(|| format!("unknown structure {}", value))
+    }
+}
+
+impl Resource {
+    pub const ALL: [Resource; 3] = [Resource::Solar, Resource::Metal, Resource::Crystal];
+
+    pub fn index(self) -> U256 {
+        U256::from(self as u8)
+    }
+
+    /// Name of the resource on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Resource::Solar => "solar",
+            Resource::Metal => "metal",
+            Resource::Crystal => "crystal",
+        }
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Resource {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        Resource::ALL
+            .iter()
+            .copied()
+            .find(|resource| resource.name().eq_ignore_ascii_case(value))
+            .ok_or_else(|| format!("unknown resource {}", value))
+    }
+}
+
+/// An amount of each resource, e.g. the cost of a structure level or the pending resources of a planet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Resources {
+    pub solar: U256,
+    pub metal: U256,
+    pub crystal: U256,
+}
+
+impl Resources {
+    /// `amount` of `resource` and nothing of the others.
+    pub fn only(resource: Resource, amount: U256) -> Resources {
+        let mut resources = Resources::default();
+        match resource {
+            Resource::Solar => resources.solar = amount,
+            Resource::Metal => resources.metal = amount,
+            Resource::Crystal => resources.crystal = amount,
+        }
+        resources
+    }
+
+    /// Resources from the costs returned by `resourceInfo` : solar, metal then crystal.
+    pub fn from_costs(cost: &[U256]) -> web3::contract::Result<Resources> {
+        match cost {
+            [solar, metal, crystal, ..] => Ok(Resources { solar: *solar, metal: *metal, crystal: *crystal }),
+            _ => Err(web3::contract::Error::InvalidOutputType(format!("expected 3 costs from resourceInfo, got {}", cost.len()))),
+        }
+    }
+
+    pub fn get(&self, resource: Resource) -> U256 {
+        match resource {
+            Resource::Solar => self.solar,
+            Resource::Metal => self.metal,
+            Resource::Crystal => self.crystal,
+        }
+    }
+
+    /// Whether there is at least `cost` of every resource.
+    pub fn covers(&self, cost: &Resources) -> bool {
+        cost.solar <= self.solar && cost.metal <= self.metal && cost.crystal <= self.crystal
+    }
+
+    /// What is left after spending `cost`, each resource bottoming out at zero.
+    pub fn saturating_sub(&self, cost: &Resources) -> Resources {
+        Resources {
+            solar: self.solar.saturating_sub(cost.solar),
+            metal: self.metal.saturating_sub(cost.metal),
+            crystal: self.crystal.saturating_sub(cost.crystal),
+        }
+    }
+}
+
+impl fmt::Display for Resources {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} s / {} m / {} c", format_decimals(self.solar), format_decimals(self.metal), format_decimals(self.crystal))
+    }
+}
+
+impl Add for Resources {
+    type Output = Resources;
+
+    fn add(self, other: Resources) -> Resources {
+        Resources {
+            solar: self.solar + other.solar,
+            metal: self.metal + other.metal,
+            crystal: self.crystal + other.crystal,
+        }
+    }
+}
+
+/// The NovaX game contract, holding the structures and resources logic.
+#[derive(Debug, Clone)]
+pub struct NovaxGame<T: Transport = WebSocket> {
+    contract: Contract<T>,
+}
+
+impl<T: Transport> NovaxGame<T> {
+    pub fn new(eth: Eth<T>, address: Address) -> Self {
+        NovaxGame { contract: Contract::from_json(eth, address, NOVAX_GAME_ABI).expect("embedded game ABI is valid") }
+    }
+
+    pub fn address(&self) -> Address {
+        self.contract.address()
+    }
+
+    pub fn abi(&self) -> &ethabi::Contract {
+        self.contract.abi()
+    }
+
+    /// Resources needed to bring `structure` to `level`.
+    pub async fn resource_info(&self, structure: Structure, level: u32) -> web3::contract::Result<Resources> {
+        let cost: Vec<U256> = self.contract.query("resourceInfo", (structure.id().to_string(), U256::from(level)), None, Options::default(), None).await?;
+        Resources::from_costs(&cost)
+    }
+
+    /// `resourceInfo(structure, level)`, to be batched. Its result is read by [`Resources::from_costs`].
+    pub fn resource_info_read(&self, structure: Structure, level: u32) -> ContractRead {
+        read(&self.contract, "resourceInfo", &[Token::String(structure.id().to_string()), Token::Uint(U256::from(level))])
+    }
+
+    /// Pending (not harvested yet) amount of `resource` on the planet.
+    pub async fn get_resource_amount(&self, resource: Resource, planet_no: U256) -> web3::contract::Result<U256> {
+        self.contract.query("getResourceAmount", (resource.index(), planet_no), None, Options::default(), None).await
+    }
+
+    /// `getResourceAmount(resource, planet)`, to be batched.
+    pub fn get_resource_amount_read(&self, resource: Resource, planet_no: U256) -> ContractRead {
+        read(&self.contract, "getResourceAmount", &[Token::Uint(resource.index()), Token::Uint(planet_no)])
+    }
+
+    /// Price of one planet, in wei.
+    pub async fn planet_fee(&self) -> web3::contract::Result<U256> {
+        self.contract.query("planetFee", (), None, Options::default(), None).await
+    }
+
+    /// Calldata of `createPlanet(amount)`, minting `amount` planets to the sender.
+    pub fn create_planet(&self, amount: U256) -> Bytes {
+        encode_call(&self.contract, "createPlanet", &[Token::Uint(amount)])
+    }
+
+    /// Calldata of `levelUpStructure(structureId, planetNo)`.
+    pub fn level_up_structure(&self, structure: Structure, planet_no: U256) -> Bytes {
+        encode_call(&self.contract, "levelUpStructure", &[Token::String(structure.id().to_string()), Token::Uint(planet_no)])
+    }
+
+    /// Calldata of `buildStructure(structureId, planetNo)`, the first level of a structure the planet does not have.
+    pub fn build_structure(&self, structure: Structure, planet_no: U256) -> Bytes {
+        encode_call(&self.contract, "buildStructure", &[Token::String(structure.id().to_string()), Token::Uint(planet_no)])
+    }
+
+    /// Calldata of `withdrawResource(resourceIndex, amount, planetNo)`.
+    pub fn withdraw_resource(&self, resource: Resource, amount: U256, planet_no: U256) -> Bytes {
+        encode_call(&self.contract, "withdrawResource", &[Token::Uint(resource.index()), Token::Uint(amount), Token::Uint(planet_no)])
+    }
+
+    /// Calldata of `harvestAll(ids)`.
+    pub fn harvest_all(&self, planet_ids: &[U256]) -> Bytes {
+        let ids = planet_ids.iter().map(|id| Token::Uint(*id)).collect();
+        encode_call(&self.contract, "harvestAll", &[Token::Array(ids)])
+    }
+}
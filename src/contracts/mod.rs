@@ -0,0 +1,136 @@
+//! Typed bindings over the NovaX contracts.
+//!
+//! The ABIs are embedded in the binary, and every contract function the tool uses is exposed as a typed method.
+//! The methods look the functions up by name : the tests encode each of them against the embedded ABIs, so a typo
+//! in a function name or a wrong argument type is caught by `cargo test`. The bindings are generic over the
+//! transport, the WebSocket one by default.
+
+mod erc20;
+mod game;
+mod planet;
+
+pub use erc20::Erc20;
+pub use game::{NovaxGame, Resource, Resources, Structure};
+pub use planet::NovaxPlanet;
+
+use web3::contract::Contract;
+use web3::Transport;
+use web3::ethabi::Token;
+use web3::types::Bytes;
+use crate::multicall::ContractRead;
+
+pub const ERC20_ABI: &[u8] = include_bytes!("../../abi/erc20.abi");
+pub const NOVAX_GAME_ABI: &[u8] = include_bytes!("../../abi/novax_game.abi");
+pub const NOVAX_PLANET_ABI: &[u8] = include_bytes!("../../abi/novax_planet.abi");
+
+/// Encode the calldata of a call to `function`, picking the overload taking as many arguments as `tokens`. The
+/// arguments are built by the typed methods, so they always match the embedded ABI.
+fn encode_call<T: Transport>(contract: &Contract<T>, function: &str, tokens: &[Token]) -> Bytes {
+    read(contract, function, tokens).data
+}
+
+/// A read of `function`, to be batched with others by [`crate::multicall::Multicall`].
+fn read<T: Transport>(contract: &Contract<T>, function: &str, tokens: &[Token]) -> ContractRead {
+    let overloads = contract.abi().functions_by_name(function).unwrap_or_else(|err| panic!("{} is not in the embedded ABI : {}", function, err));
+    let overload = overloads.iter().find(|overload| overload.inputs.len() == tokens.len()).unwrap_or(&overloads[0]);
+    ContractRead::new(contract.address(), overload, tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use web3::Web3;
+    use web3::api::Eth;
+    use web3::ethabi::{self, Address};
+    use web3::ethabi::ethereum_types::U256;
+    use web3::transports::Http;
+    use super::*;
+
+    /// A node that is never reached : the queries fail with a transport error once their call is encoded.
+    fn offline() -> Eth<Http> {
+        Web3::new(Http::new("http://127.0.0.1:1").unwrap()).eth()
+    }
+
+    /// `data` calls `function` of `abi`, with arguments matching one of its overloads.
+    fn assert_calls(abi: &ethabi::Contract, function: &str, data: &[u8]) {
+        let overload = abi.functions_by_name(function).unwrap().iter().find(|overload| overload.short_signature() == data[..4]);
+        let overload = overload.unwrap_or_else(|| panic!("calldata does not call any overload of {}", function));
+        overload.decode_input(&data[4..]).unwrap_or_else(|err| panic!("invalid arguments of {} : {}", function, err));
+    }
+
+    fn assert_reads(abi: &ethabi::Contract, address: Address, function: &str, read: &ContractRead) {
+        assert_eq!(read.to, address);
+        assert_calls(abi, function, &read.data.0);
+    }
+
+    /// The query was encoded against the embedded ABI and only failed to reach the node.
+    fn assert_queried<R: std::fmt::Debug>(function: &str, result: web3::contract::Result<R>) {
+        match result {
+            Err(web3::contract::Error::Api(_)) => {}
+            result => panic!("{} was not encoded against the embedded ABI : {:?}", function, result),
+        }
+    }
+
+    #[tokio::test]
+    async fn game_bindings_match_the_embedded_abi() {
+        let address = Address::from_low_u64_be(1);
+        let game = NovaxGame::new(offline(), address);
+        let abi = game.abi().clone();
+        let planet_no = U256::from(12);
+
+        for structure in Structure::ALL {
+            assert_queried("resourceInfo", game.resource_info(structure, 3).await);
+            assert_reads(&abi, address, "resourceInfo", &game.resource_info_read(structure, 3));
+            assert_calls(&abi, "levelUpStructure", &game.level_up_structure(structure, planet_no).0);
+            assert_calls(&abi, "buildStructure", &game.build_structure(structure, planet_no).0);
+        }
+        for resource in Resource::ALL {
+            assert_queried("getResourceAmount", game.get_resource_amount(resource, planet_no).await);
+            assert_reads(&abi, address, "getResourceAmount", &game.get_resource_amount_read(resource, planet_no));
+            assert_calls(&abi, "withdrawResource", &game.withdraw_resource(resource, U256::exp10(18), planet_no).0);
+        }
+        assert_queried("planetFee", game.planet_fee().await);
+        assert_calls(&abi, "createPlanet", &game.create_planet(U256::from(2)).0);
+        assert_calls(&abi, "harvestAll", &game.harvest_all(&[planet_no, U256::from(13)]).0);
+    }
+
+    #[tokio::test]
+    async fn planet_bindings_match_the_embedded_abi() {
+        let address = Address::from_low_u64_be(2);
+        let planet = NovaxPlanet::new(offline(), address);
+        let abi = planet.abi().clone();
+        let (owner, operator, token_id) = (Address::from_low_u64_be(3), Address::from_low_u64_be(4), U256::from(12));
+
+        assert_queried("tokensOfOwner", planet.tokens_of_owner(owner).await);
+        assert_queried("ownerOf", planet.owner_of(token_id).await);
+        assert_queried("getApproved", planet.get_approved(token_id).await);
+        assert_queried("isApprovedForAll", planet.is_approved_for_all(owner, operator).await);
+        assert_queried("MAX_PLANETS", planet.max_planets().await);
+        assert_queried("tokenURI", planet.token_uri(token_id).await);
+        assert_reads(&abi, address, "tokenURI", &planet.token_uri_read(token_id));
+        assert_calls(&abi, "transferFrom", &planet.transfer_from(owner, operator, token_id).0);
+        assert_calls(&abi, "approve", &planet.approve(operator, token_id).0);
+        assert_calls(&abi, "setApprovalForAll", &planet.set_approval_for_all(operator, true).0);
+
+        let without_data = planet.safe_transfer_from(owner, operator, token_id, None);
+        let with_data = planet.safe_transfer_from(owner, operator, token_id, Some(b"hello"));
+        assert_calls(&abi, "safeTransferFrom", &without_data.0);
+        assert_calls(&abi, "safeTransferFrom", &with_data.0);
+        assert_ne!(without_data.0[..4], with_data.0[..4], "both overloads of safeTransferFrom should be used");
+    }
+
+    #[tokio::test]
+    async fn erc20_bindings_match_the_embedded_abi() {
+        let address = Address::from_low_u64_be(5);
+        let token = Erc20::new(offline(), address);
+        let abi = token.abi().clone();
+        let (owner, spender, amount) = (Address::from_low_u64_be(6), Address::from_low_u64_be(7), U256::exp10(18));
+
+        assert_queried("balanceOf", token.balance_of(owner).await);
+        assert_reads(&abi, address, "balanceOf", &token.balance_of_read(owner));
+        assert_queried("allowance", token.allowance(owner, spender).await);
+        assert_calls(&abi, "transfer", &token.transfer(spender, amount).0);
+        assert_calls(&abi, "approve", &token.approve(spender, amount).0);
+        assert_calls(&abi, "increaseAllowance", &token.increase_allowance(spender, amount).0);
+        assert_calls(&abi, "decreaseAllowance", &token.decrease_allowance(spender, amount).0);
+    }
+}
@@ -0,0 +1,88 @@
+use web3::api::Eth;
+use web3::contract::{Contract, Options};
+use web3::ethabi::{self, Address, Token};
+use web3::ethabi::ethereum_types::U256;
+use web3::Transport;
+use web3::transports::WebSocket;
+use web3::types::Bytes;
+use crate::multicall::ContractRead;
+use super::{encode_call, read, NOVAX_PLANET_ABI};
+
+/// The planet NFT (ERC721) contract.
+#[derive(Debug, Clone)]
+pub struct NovaxPlanet<T: Transport = WebSocket> {
+    contract: Contract<T>,
+}
+
+impl<T: Transport> NovaxPlanet<T> {
+    pub fn new(eth: Eth<T>, address: Address) -> Self {
+        NovaxPlanet { contract: Contract::from_json(eth, address, NOVAX_PLANET_ABI).expect("embedded planet ABI is valid") }
+    }
+
+    pub fn address(&self) -> Address {
+        self.contract.address()
+    }
+
+    pub fn abi(&self) -> &ethabi::Contract {
+        self.contract.abi()
+    }
+
+    /// Ids of the planets owned by `owner`.
+    pub async fn tokens_of_owner(&self, owner: Address) -> web3::contract::Result<Vec<U256>> {
+        self.contract.query("tokensOfOwner", owner, None, Options::default(), None).await
+    }
+
+    /// Owner of the planet. The call reverts when the planet was not minted.
+    pub async fn owner_of(&self, token_id: U256) -> web3::contract::Result<Address> {
+        self.contract.query("ownerOf", token_id, None, Options::default(), None).await
+    }
+
+    /// Address allowed to transfer the planet on behalf of its owner, zero when there is none.
+    pub async fn get_approved(&self, token_id: U256) -> web3::contract::Result<Address> {
+        self.contract.query("getApproved", token_id, None, Options::default(), None).await
+    }
+
+    /// Whether `operator` may transfer every planet of `owner`.
+    pub async fn is_approved_for_all(&self, owner: Address, operator: Address) -> web3::contract::Result<bool> {
+        self.contract.query("isApprovedForAll", (owner, operator), None, Options::default(), None).await
+    }
+
+    /// Number of planets that can ever be minted.
+    pub async fn max_planets(&self) -> web3::contract::Result<U256> {
+        self.contract.query("MAX_PLANETS", (), None, Options::default(), None).await
+    }
+
+    /// URL of the JSON metadata of the planet.
+    pub async fn token_uri(&self, token_id: U256) -> web3::contract::Result<String> {
+        self.contract.query("tokenURI", token_id, None, Options::default(), None).await
+    }
+
+    /// `tokenURI(tokenId)`, to be batched.
+    pub fn token_uri_read(&self, token_id: U256) -> ContractRead {
+        read(&self.contract, "tokenURI", &[Token::Uint(token_id)])
+    }
+
+    /// Calldata of `transferFrom(from, to, tokenId)`, which does not check that a contract recipient accepts NFTs.
+    pub fn transfer_from(&self, from: Address, to: Address, token_id: U256) -> Bytes {
+        encode_call(&self.contract, "transferFrom", &[Token::Address(from), Token::Address(to), Token::Uint(token_id)])
+    }
+
+    /// Calldata of `safeTransferFrom(from, to, tokenId)`, or of the overload passing `data` to the recipient.
+    pub fn safe_transfer_from(&self, from: Address, to: Address, token_id: U256, data: Option<&[u8]>) -> Bytes {
+        let mut tokens = vec![Token::Address(from), Token::Address(to), Token::Uint(token_id)];
+        if let Some(data) = data {
+            tokens.push(Token::Bytes(data.to_vec()));
+        }
+        encode_call(&self.contract, "safeTransferFrom", &tokens)
+    }
+
+    /// Calldata of `approve(to, tokenId)`, the zero address revoking the approval of the planet.
+    pub fn approve(&self, to: Address, token_id: U256) -> Bytes {
+        encode_call(&self.contract, "approve", &[Token::Address(to), Token::Uint(token_id)])
+    }
+
+    /// Calldata of `setApprovalForAll(operator, approved)`.
+    pub fn set_approval_for_all(&self, operator: Address, approved: bool) -> Bytes {
+        encode_call(&self.contract, "setApprovalForAll", &[Token::Address(operator), Token::Bool(approved)])
+    }
+}
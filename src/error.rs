@@ -0,0 +1,116 @@
+use std::fmt::Display;
+use std::time::Duration;
+use thiserror::Error;
+use web3::types::{TransactionReceipt, H256, U256};
+use crate::contracts::Resources;
+use crate::utils::format_decimals;
+
+pub type Result<T> = std::result::Result<T, NovaxError>;
+
+/// Everything that can go wrong while talking to the NovaX contracts.
+///
+/// Each kind of failure maps to its own process exit code (see [`NovaxError::exit_code`]) so scripts running the
+/// CLI can react differently to, say, a flaky RPC endpoint and a reverted transaction.
+#[derive(Debug, Error)]
+pub enum NovaxError {
+    /// Invalid configuration file, profile or command line arguments
+    #[error("{0}")]
+    Config(String),
+    /// The key could not be loaded, decrypted or stored
+    #[error("keystore error : {0}")]
+    Keystore(String),
+    /// The node could not be reached, or answered with an error
+    #[error("RPC error : {0}")]
+    Rpc(#[from] web3::Error),
+    /// A contract call could not be encoded, or its result decoded
+    #[error("ABI error : {0}")]
+    Abi(String),
+    /// The planet metadata could not be fetched or parsed
+    #[error("planet metadata error : {0}")]
+    Metadata(String),
+    /// The gas usage of a transaction could not be estimated
+    #[error("gas estimation failed : {0}")]
+    GasEstimation(String),
+    /// The transaction was mined but reverted
+    #[error("transaction {tx_hash:?} reverted{}", .reason.as_ref().map(|reason| format!(" : {}", reason)).unwrap_or_default())]
+    Reverted { tx_hash: H256, reason: Option<String>, receipt: Box<TransactionReceipt> },
+    /// The transaction was sent but not mined (or not confirmed) in time
+    #[error("transaction {tx_hash:?} not confirmed after {}s", .timeout.as_secs())]
+    ReceiptTimeout { tx_hash: H256, timeout: Duration },
+    /// The transaction was dropped by the node, or its nonce was used by another transaction
+    #[error("transaction {tx_hash:?} with nonce {nonce} was dropped or replaced by another transaction")]
+    TxDropped { tx_hash: H256, nonce: U256 },
+    /// The base fee of the network stayed above the configured fee ceiling
+    #[error("network base fee {base_fee} wei is still above the fee ceiling of {cap} wei")]
+    FeeAboveCap { base_fee: U256, cap: U256 },
+    /// The wallet does not hold enough resources for an action
+    #[error("insufficient resources : {needed} needed but only {available} available")]
+    InsufficientResources { needed: Box<Resources>, available: Box<Resources> },
+    /// The wallet does not hold enough AVAX for the value and gas of a transaction
+    #[error("insufficient funds : {} AVAX needed but only {} AVAX available", format_decimals(*.needed), format_decimals(*.available))]
+    InsufficientFunds { needed: U256, available: U256 },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl NovaxError {
+    /// Exit code of the CLI when it fails with this error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            NovaxError::Io(_) => 1,
+            NovaxError::Config(_) => 2,
+            NovaxError::Keystore(_) => 3,
+            NovaxError::Rpc(_) => 4,
+            NovaxError::Abi(_) => 5,
+            NovaxError::Metadata(_) => 6,
+            NovaxError::GasEstimation(_) => 7,
+            NovaxError::Reverted { .. } => 8,
+            NovaxError::InsufficientResources { .. } => 9,
+            NovaxError::ReceiptTimeout { .. } => 10,
+            NovaxError::FeeAboveCap { .. } => 11,
+            NovaxError::TxDropped { .. } => 12,
+            NovaxError::InsufficientFunds { .. } => 13,
+        }
+    }
+
+    pub fn config(message: impl Display) -> NovaxError {
+        NovaxError::Config(message.to_string())
+    }
+
+    pub fn keystore(message: impl Display) -> NovaxError {
+        NovaxError::Keystore(message.to_string())
+    }
+}
+
+impl From<web3::contract::Error> for NovaxError {
+    fn from(err: web3::contract::Error) -> Self {
+        match err {
+            web3::contract::Error::Api(err) => NovaxError::Rpc(err),
+            err => NovaxError::Abi(err.to_string()),
+        }
+    }
+}
+
+impl From<web3::ethabi::Error> for NovaxError {
+    fn from(err: web3::ethabi::Error) -> Self {
+        NovaxError::Abi(err.to_string())
+    }
+}
+
+impl From<reqwest::Error> for NovaxError {
+    fn from(err: reqwest::Error) -> Self {
+        NovaxError::Metadata(err.to_string())
+    }
+}
+
+impl From<eth_keystore::KeystoreError> for NovaxError {
+    fn from(err: eth_keystore::KeystoreError) -> Self {
+        NovaxError::Keystore(err.to_string())
+    }
+}
+
+impl From<secp256k1::Error> for NovaxError {
+    fn from(err: secp256k1::Error) -> Self {
+        NovaxError::Keystore(err.to_string())
+    }
+}
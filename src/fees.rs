@@ -0,0 +1,215 @@
+//! Gas fees of the transactions : a fixed legacy gas price, or EIP-1559 fees picked by the [gas oracle](crate::gas_oracle).
+
+use std::fmt;
+use web3::Web3;
+use web3::ethabi::ethereum_types::{U256, U64};
+use web3::transports::WebSocket;
+use web3::types::{CallRequest, TransactionParameters};
+use crate::error::Result;
+use crate::gas_oracle::{base_fee, GasOracle};
+
+/// How the fees of a transaction are chosen.
+#[derive(Debug, Clone, Copy)]
+pub enum FeePolicy {
+    /// Legacy transaction with a fixed gas price, in wei
+    Legacy(U256),
+    /// Type-2 transaction priced by the gas oracle
+    Oracle(GasOracle),
+}
+
+/// Fees of one transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasFees {
+    Legacy { gas_price: U256 },
+    Eip1559 { max_fee_per_gas: U256, max_priority_fee_per_gas: U256 },
+}
+
+impl Default for FeePolicy {
+    fn default() -> Self {
+        FeePolicy::Oracle(GasOracle::default())
+    }
+}
+
+impl FeePolicy {
+    /// Fees to use for a transaction sent now, see [`GasOracle::fees`].
+    pub async fn fees(&self, web3: &Web3<WebSocket>) -> Result<GasFees> {
+        match self {
+            FeePolicy::Legacy(gas_price) => Ok(GasFees::Legacy { gas_price: *gas_price }),
+            FeePolicy::Oracle(oracle) => oracle.fees(web3).await,
+        }
+    }
+
+    /// Price per gas a transaction sent now is expected to pay, see [`GasFees::effective_price`].
+    pub async fn expected_price(&self, web3: &Web3<WebSocket>) -> Result<U256> {
+        match self.fees(web3).await? {
+            GasFees::Legacy { gas_price } => Ok(gas_price),
+            fees => Ok(fees.effective_price(base_fee(web3).await?)),
+        }
+    }
+
+    /// Highest price per gas the transactions may pay, when there is one, see [`GasOracle::max_fee_cap`].
+    pub fn max_fee_cap(&self) -> Option<U256> {
+        match self {
+            FeePolicy::Legacy(_) => None,
+            FeePolicy::Oracle(oracle) => oracle.max_fee_cap(),
+        }
+    }
+}
+
+impl GasFees {
+    /// Highest price per gas the transaction may pay.
+    pub fn max_price(&self) -> U256 {
+        match *self {
+            GasFees::Legacy { gas_price } => gas_price,
+            GasFees::Eip1559 { max_fee_per_gas, .. } => max_fee_per_gas,
+        }
+    }
+
+    /// Price per gas actually paid in a block with `base_fee` : the base fee plus the priority fee, up to the max fee.
+    pub fn effective_price(&self, base_fee: U256) -> U256 {
+        match *self {
+            GasFees::Legacy { gas_price } => gas_price,
+            GasFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => base_fee.saturating_add(max_priority_fee_per_gas).min(max_fee_per_gas),
+        }
+    }
+
+    /// Fees raised by `percent` percent (and at least one wei), to replace a pending transaction.
+    pub fn bumped(&self, percent: u64) -> GasFees {
+        let bump = |fee: U256| fee.saturating_add((fee.saturating_mul(U256::from(percent)) / 100).max(U256::one()));
+        match *self {
+            GasFees::Legacy { gas_price } => GasFees::Legacy { gas_price: bump(gas_price) },
+            GasFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => {
+                GasFees::Eip1559 { max_fee_per_gas: bump(max_fee_per_gas), max_priority_fee_per_gas: bump(max_priority_fee_per_gas) }
+            }
+        }
+    }
+
+    /// These fees lowered to pay at most `cap` per gas.
+    pub fn capped(&self, cap: U256) -> GasFees {
+        match *self {
+            GasFees::Legacy { gas_price } => GasFees::Legacy { gas_price: gas_price.min(cap) },
+            GasFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => {
+                GasFees::Eip1559 { max_fee_per_gas: max_fee_per_gas.min(cap), max_priority_fee_per_gas: max_priority_fee_per_gas.min(cap) }
+            }
+        }
+    }
+
+    /// The highest of these fees and `other`, field by field, so a transaction sent again never pays less than the
+    /// first time. Fees of another kind than `other` are kept as they are.
+    pub fn at_least(&self, other: &GasFees) -> GasFees {
+        match (*self, *other) {
+            (GasFees::Legacy { gas_price }, GasFees::Legacy { gas_price: other }) => GasFees::Legacy { gas_price: gas_price.max(other) },
+            (GasFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas }, GasFees::Eip1559 { max_fee_per_gas: other_max_fee, max_priority_fee_per_gas: other_priority_fee }) => {
+                GasFees::Eip1559 { max_fee_per_gas: max_fee_per_gas.max(other_max_fee), max_priority_fee_per_gas: max_priority_fee_per_gas.max(other_priority_fee) }
+            }
+            (fees, _) => fees,
+        }
+    }
+
+    pub fn apply_to_transaction(&self, transaction: &mut TransactionParameters) {
+        match *self {
+            GasFees::Legacy { gas_price } => {
+                transaction.gas_price = Some(gas_price);
+                transaction.transaction_type = None;
+            }
+            GasFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => {
+                transaction.gas_price = None;
+                transaction.max_fee_per_gas = Some(max_fee_per_gas);
+                transaction.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+                transaction.transaction_type = Some(U64::from(2));
+            }
+        }
+    }
+
+    pub fn apply_to_call(&self, call: &mut CallRequest) {
+        match *self {
+            GasFees::Legacy { gas_price } => {
+                call.gas_price = Some(gas_price);
+                call.transaction_type = None;
+            }
+            GasFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => {
+                call.gas_price = None;
+                call.max_fee_per_gas = Some(max_fee_per_gas);
+                call.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+                call.transaction_type = Some(U64::from(2));
+            }
+        }
+    }
+}
+
+impl fmt::Display for GasFees {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GasFees::Legacy { gas_price } => write!(f, "legacy, gas price {} wei", gas_price),
+            GasFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas } => {
+                write!(f, "type-2, max fee {} wei, priority fee {} wei", max_fee_per_gas, max_priority_fee_per_gas)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eip1559(max_fee_per_gas: u64, max_priority_fee_per_gas: u64) -> GasFees {
+        GasFees::Eip1559 { max_fee_per_gas: U256::from(max_fee_per_gas), max_priority_fee_per_gas: U256::from(max_priority_fee_per_gas) }
+    }
+
+    #[test]
+    fn effective_price_is_the_base_fee_plus_the_priority_fee_up_to_the_max_fee() {
+        assert_eq!(eip1559(1000, 50).effective_price(U256::from(400)), U256::from(450));
+        assert_eq!(eip1559(1000, 50).effective_price(U256::from(990)), U256::from(1000));
+        assert_eq!(GasFees::Legacy { gas_price: U256::from(7) }.effective_price(U256::from(400)), U256::from(7));
+    }
+
+    #[test]
+    fn bumped_raises_every_fee_by_the_percentage() {
+        assert_eq!(GasFees::Legacy { gas_price: U256::from(1000) }.bumped(15), GasFees::Legacy { gas_price: U256::from(1150) });
+        assert_eq!(eip1559(1000, 200).bumped(10), eip1559(1100, 220));
+    }
+
+    #[test]
+    fn bumped_raises_by_at_least_one_wei() {
+        assert_eq!(eip1559(0, 5).bumped(10), eip1559(1, 6));
+        assert_eq!(GasFees::Legacy { gas_price: U256::from(1000) }.bumped(0), GasFees::Legacy { gas_price: U256::from(1001) });
+    }
+
+    #[test]
+    fn bump_of_an_already_bumped_fee_stays_above_the_replacement_minimum() {
+        // Nodes only accept a replacement paying at least 10% more than the transaction it replaces.
+        let mut fees = eip1559(25_000_000_000, 1_500_000_000);
+        for _ in 0..5 {
+            let bumped = fees.bumped(10);
+            match (fees, bumped) {
+                (GasFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas }, GasFees::Eip1559 { max_fee_per_gas: bumped_max_fee, max_priority_fee_per_gas: bumped_priority_fee }) => {
+                    assert!(bumped_max_fee * 100 >= max_fee_per_gas * 110);
+                    assert!(bumped_priority_fee * 100 >= max_priority_fee_per_gas * 110);
+                }
+                _ => panic!("bumped changed the kind of fees"),
+            }
+            fees = bumped;
+        }
+        assert_eq!(eip1559(1000, 100).bumped(10).bumped(10), eip1559(1210, 121));
+    }
+
+    #[test]
+    fn bumped_saturates() {
+        assert_eq!(GasFees::Legacy { gas_price: U256::MAX }.bumped(50), GasFees::Legacy { gas_price: U256::MAX });
+    }
+
+    #[test]
+    fn capped_lowers_the_fees_above_the_cap() {
+        assert_eq!(eip1559(1000, 50).capped(U256::from(800)), eip1559(800, 50));
+        assert_eq!(eip1559(1000, 50).capped(U256::from(30)), eip1559(30, 30));
+        assert_eq!(eip1559(1000, 50).capped(U256::from(2000)), eip1559(1000, 50));
+        assert_eq!(GasFees::Legacy { gas_price: U256::from(7) }.capped(U256::from(5)), GasFees::Legacy { gas_price: U256::from(5) });
+    }
+
+    #[test]
+    fn at_least_keeps_the_highest_fees() {
+        assert_eq!(eip1559(1000, 50).at_least(&eip1559(900, 80)), eip1559(1000, 80));
+        assert_eq!(GasFees::Legacy { gas_price: U256::from(5) }.at_least(&GasFees::Legacy { gas_price: U256::from(7) }), GasFees::Legacy { gas_price: U256::from(7) });
+        assert_eq!(eip1559(1000, 50).at_least(&GasFees::Legacy { gas_price: U256::from(5000) }), eip1559(1000, 50));
+    }
+}
@@ -0,0 +1,211 @@
+//! Gas oracle pricing type-2 transactions from the state of the network.
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use web3::Web3;
+use web3::ethabi::ethereum_types::U256;
+use web3::transports::WebSocket;
+use web3::types::BlockNumber;
+use crate::error::{NovaxError, Result};
+use crate::fees::GasFees;
+
+/// Number of blocks of fee history the priority fee is derived from.
+const FEE_HISTORY_BLOCKS: u64 = 10;
+/// Percentiles of the priority fees paid in each block of the history, for the standard and fast strategies.
+const REWARD_PERCENTILES: [f64; 2] = [50.0, 90.0];
+/// Delay between two checks of the base fee while it is above the ceiling.
+const CEILING_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How aggressively the transactions are priced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasStrategy {
+    /// Median priority fee of the recent blocks
+    Standard,
+    /// 90th percentile priority fee of the recent blocks
+    Fast,
+    /// Fixed max fee per gas, in wei, with the median priority fee
+    Max(U256),
+}
+
+/// Picks the fees of type-2 transactions.
+#[derive(Debug, Clone, Copy)]
+pub struct GasOracle {
+    pub strategy: GasStrategy,
+    /// Fees above this max fee per gas are never paid : the oracle waits for the base fee to drop below it
+    pub ceiling: Option<U256>,
+    /// Priority fee to use instead of the one of the strategy, in wei
+    pub priority_fee: Option<U256>,
+    /// How long to wait for the base fee to drop below the ceiling before giving up
+    pub max_wait: Duration,
+}
+
+impl Default for GasOracle {
+    fn default() -> Self {
+        GasOracle { strategy: GasStrategy::Standard, ceiling: None, priority_fee: None, max_wait: Duration::from_secs(600) }
+    }
+}
+
+impl GasOracle {
+    /// Highest max fee per gas the oracle may pick : the lowest of the ceiling and the max fee of the strategy.
+    pub fn max_fee_cap(&self) -> Option<U256> {
+        match self.strategy {
+            GasStrategy::Max(max_fee) => Some(self.ceiling.map_or(max_fee, |ceiling| ceiling.min(max_fee))),
+            GasStrategy::Standard | GasStrategy::Fast => self.ceiling,
+        }
+    }
+
+    /// Fees to use for a transaction sent now.
+    ///
+    /// While the base fee of the next block is above the ceiling the oracle waits, and fails with
+    /// [`NovaxError::FeeAboveCap`] if it is still above after `max_wait`.
+    pub async fn fees(&self, web3: &Web3<WebSocket>) -> Result<GasFees> {
+        let started = Instant::now();
+        loop {
+            let estimate = FeeEstimate::fetch(web3).await?;
+            let priority_fee = self.priority_fee.unwrap_or(match self.strategy {
+                GasStrategy::Fast => estimate.fast_priority_fee,
+                GasStrategy::Standard | GasStrategy::Max(_) => estimate.priority_fee,
+            });
+            // Twice the base fee keeps the transaction valid through several full blocks in a row.
+            let wanted_max_fee = match self.strategy {
+                GasStrategy::Max(max_fee) => max_fee,
+                GasStrategy::Standard | GasStrategy::Fast => estimate.base_fee.saturating_mul(U256::from(2)).saturating_add(priority_fee),
+            };
+            match self.max_fee_cap() {
+                Some(ceiling) if ceiling < estimate.base_fee => {
+                    if started.elapsed() >= self.max_wait {
+                        return Err(NovaxError::FeeAboveCap { base_fee: estimate.base_fee, cap: ceiling });
+                    }
+                    println!("{:?} -- Base fee {} wei is above the ceiling of {} wei, waiting", Instant::now(), estimate.base_fee, ceiling);
+                    tokio::time::sleep(CEILING_POLL_INTERVAL).await;
+                }
+                ceiling => {
+                    let max_fee_per_gas = ceiling.map_or(wanted_max_fee, |ceiling| wanted_max_fee.min(ceiling));
+                    return Ok(GasFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas: priority_fee.min(max_fee_per_gas) });
+                }
+            }
+        }
+    }
+}
+
+/// Base fee of the next block, or the gas price of the node when it does not serve `eth_feeHistory`.
+pub async fn base_fee(web3: &Web3<WebSocket>) -> Result<U256> {
+    Ok(FeeEstimate::fetch(web3).await?.base_fee)
+}
+
+/// Base fee of the next block and recent priority fees.
+struct FeeEstimate {
+    base_fee: U256,
+    priority_fee: U256,
+    fast_priority_fee: U256,
+}
+
+impl FeeEstimate {
+    /// Read the fee history, falling back on `eth_gasPrice` (and no priority fee) when the node does not serve
+    /// `eth_feeHistory`.
+    async fn fetch(web3: &Web3<WebSocket>) -> Result<FeeEstimate> {
+        let history = web3.eth().fee_history(U256::from(FEE_HISTORY_BLOCKS), BlockNumber::Latest, Some(REWARD_PERCENTILES.to_vec())).await;
+        if let Ok(history) = history {
+            if let Some(base_fee) = history.base_fee_per_gas.last() {
+                let rewards = history.reward.unwrap_or_default();
+                return Ok(FeeEstimate {
+                    base_fee: *base_fee,
+                    priority_fee: median(rewards.iter().filter_map(|reward| reward.first().copied()).collect()),
+                    fast_priority_fee: median(rewards.iter().filter_map(|reward| reward.get(1).copied()).collect()),
+                });
+            }
+        }
+
+        let gas_price = web3.eth().gas_price().await?;
+        Ok(FeeEstimate { base_fee: gas_price, priority_fee: U256::zero(), fast_priority_fee: U256::zero() })
+    }
+}
+
+fn median(mut values: Vec<U256>) -> U256 {
+    values.sort();
+    values.get(values.len() / 2).copied().unwrap_or_default()
+}
+
+impl FromStr for GasStrategy {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "standard" => Ok(GasStrategy::Standard),
+            "fast" => Ok(GasStrategy::Fast),
+            _ => match value.strip_prefix("max=") {
+                Some(amount) => Ok(GasStrategy::Max(parse_gas_amount(amount)?)),
+                None => Err(format!("unknown gas strategy {}, expected standard, fast or max=AMOUNT", value)),
+            },
+        }
+    }
+}
+
+impl fmt::Display for GasStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GasStrategy::Standard => write!(f, "standard"),
+            GasStrategy::Fast => write!(f, "fast"),
+            GasStrategy::Max(max_fee) => write!(f, "max={}wei", max_fee),
+        }
+    }
+}
+
+/// Parse a price per gas such as `50gwei`, `25navax` or `1000000000` (wei).
+pub fn parse_gas_amount(value: &str) -> std::result::Result<U256, String> {
+    let lower = value.trim().to_ascii_lowercase();
+    let (number, multiplier) = if let Some(number) = lower.strip_suffix("gwei").or_else(|| lower.strip_suffix("navax")) {
+        (number, U256::exp10(9))
+    } else if let Some(number) = lower.strip_suffix("wei") {
+        (number, U256::one())
+    } else {
+        (lower.as_str(), U256::one())
+    };
+    // `from_dec_str` reads an empty string as zero.
+    if number.trim().is_empty() {
+        return Err(format!("invalid amount {} : no number", value));
+    }
+    let number = U256::from_dec_str(number.trim()).map_err(|err| format!("invalid amount {} : {:?}", value, err))?;
+    number.checked_mul(multiplier).ok_or_else(|| format!("amount {} is too large", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_gas_amount_reads_the_units() {
+        let gwei = U256::exp10(9);
+        assert_eq!(parse_gas_amount("1000000000"), Ok(gwei));
+        assert_eq!(parse_gas_amount("1000000000wei"), Ok(gwei));
+        assert_eq!(parse_gas_amount("50gwei"), Ok(gwei * 50));
+        assert_eq!(parse_gas_amount("25nAVAX"), Ok(gwei * 25));
+        assert_eq!(parse_gas_amount(" 30 GWEI "), Ok(gwei * 30));
+    }
+
+    #[test]
+    fn parse_gas_amount_rejects_invalid_amounts() {
+        for value in ["", "gwei", "1.5gwei", "-1", "50 avax", "0x10"] {
+            assert!(parse_gas_amount(value).is_err(), "{:?} was accepted", value);
+        }
+        assert_eq!(parse_gas_amount(&format!("{}gwei", U256::MAX)), Err(format!("amount {}gwei is too large", U256::MAX)));
+    }
+
+    #[test]
+    fn gas_strategy_parses_and_displays() {
+        assert_eq!("standard".parse(), Ok(GasStrategy::Standard));
+        assert_eq!("fast".parse(), Ok(GasStrategy::Fast));
+        assert_eq!("max=40gwei".parse(), Ok(GasStrategy::Max(U256::exp10(9) * 40)));
+        for strategy in [GasStrategy::Standard, GasStrategy::Fast, GasStrategy::Max(U256::from(123))] {
+            assert_eq!(strategy.to_string().parse(), Ok(strategy));
+        }
+    }
+
+    #[test]
+    fn gas_strategy_rejects_unknown_strategies() {
+        for value in ["", "slow", "Fast", "max=", "max=lots", "max"] {
+            assert!(value.parse::<GasStrategy>().is_err(), "{:?} was accepted", value);
+        }
+    }
+}
@@ -0,0 +1,214 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use rand::RngCore;
+use secp256k1::SecretKey;
+use serde::Deserialize;
+use web3::ethabi::Address;
+use web3::signing::{Key, SecretKeyRef};
+use crate::error::{NovaxError, Result};
+
+/// Where the private key used to sign the transactions is read from.
+#[derive(Debug, Clone)]
+pub enum KeySource {
+    /// Encrypted Ethereum V3 JSON keystore, given as a path or as a name in the keystore directory
+    Keystore(String),
+    /// Environment variable holding the hex encoded private key
+    Env(String),
+    /// Already opened file descriptor the hex encoded private key can be read from
+    Fd(i32),
+}
+
+/// Where the passphrase of an encrypted keystore is read from.
+#[derive(Debug, Clone)]
+pub enum Passphrase {
+    /// Interactive prompt on the terminal
+    Prompt,
+    /// First line of a file
+    File(PathBuf),
+}
+
+/// A keystore of the keystore directory.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KeystoreEntry {
+    pub name: String,
+    pub address: Option<Address>,
+}
+
+#[derive(Deserialize)]
+struct KeystoreHeader {
+    address: Option<String>,
+}
+
+/// Directory holding the keystores managed by `keystore import/new`.
+pub fn keystore_dir() -> PathBuf {
+    dirs::config_dir().unwrap_or_else(|| PathBuf::from(".")).join("novaxcli").join("keystore")
+}
+
+pub fn address_of(key: &SecretKey) -> Address {
+    SecretKeyRef::new(key).address()
+}
+
+/// Load the signing key from the given source, asking for the keystore passphrase if needed.
+pub fn load_key(source: &KeySource, passphrase: &Passphrase) -> Result<SecretKey> {
+    match source {
+        KeySource::Keystore(keystore) => {
+            let path = resolve_keystore(keystore)?;
+            let password = read_passphrase(passphrase, &format!("Passphrase for {}: ", path.display()))?;
+            let bytes = eth_keystore::decrypt_key(&path, password)?;
+            Ok(SecretKey::from_slice(&bytes)?)
+        }
+        KeySource::Env(variable) => {
+            let value = std::env::var(variable).map_err(|_| NovaxError::keystore(format!("environment variable {} is not set", variable)))?;
+            parse_private_key(&value)
+        }
+        KeySource::Fd(fd) => parse_private_key(&read_fd(*fd)?),
+    }
+}
+
+/// Find the wallet address of a key source, without decrypting the keystore when it records its address.
+pub fn load_address(source: &KeySource, passphrase: &Passphrase) -> Result<Address> {
+    if let KeySource::Keystore(keystore) = source {
+        if let Some(address) = read_keystore_address(&resolve_keystore(keystore)?)? {
+            return Ok(address);
+        }
+    }
+    Ok(address_of(&load_key(source, passphrase)?))
+}
+
+/// Encrypt an existing private key into the keystore directory, returning the path of the keystore.
+pub fn import(key: &SecretKey, name: Option<&str>, password: &str) -> Result<PathBuf> {
+    let address = address_of(key);
+    let dir = keystore_dir();
+    fs::create_dir_all(&dir)?;
+
+    let name = name.map(str::to_string).unwrap_or_else(|| format!("{:?}", address));
+    check_keystore_name(&name)?;
+    let path = dir.join(&name);
+    if path.exists() {
+        return Err(NovaxError::keystore(format!("a keystore named {} already exists", name)));
+    }
+
+    eth_keystore::encrypt_key(&dir, &mut rand::thread_rng(), &key[..], password, Some(&name))?;
+
+    // eth-keystore does not record the address, we add it so `keystore list` doesn't need the passphrase.
+    let mut json: serde_json::Value = serde_json::from_slice(&fs::read(&path)?).map_err(NovaxError::keystore)?;
+    json["address"] = serde_json::Value::String(hex::encode(address.as_bytes()));
+    fs::write(&path, serde_json::to_vec(&json).map_err(NovaxError::keystore)?)?;
+    Ok(path)
+}
+
+/// Generate a brand new private key and store it encrypted in the keystore directory.
+pub fn generate(name: Option<&str>, password: &str) -> Result<(Address, PathBuf)> {
+    let mut bytes = [0u8; 32];
+    let key = loop {
+        rand::thread_rng().fill_bytes(&mut bytes);
+        if let Ok(key) = SecretKey::from_slice(&bytes) {
+            break key;
+        }
+    };
+    let path = import(&key, name, password)?;
+    Ok((address_of(&key), path))
+}
+
+/// List the keystores of the keystore directory along with the address they hold, when known.
+pub fn list() -> Result<Vec<KeystoreEntry>> {
+    let dir = keystore_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut keystores = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.is_file() {
+            let name = entry_name(&path);
+            keystores.push(KeystoreEntry { name, address: read_keystore_address(&path).unwrap_or(None) });
+        }
+    }
+    keystores.sort();
+    Ok(keystores)
+}
+
+pub fn read_passphrase(passphrase: &Passphrase, prompt: &str) -> Result<String> {
+    match passphrase {
+        Passphrase::Prompt => Ok(rpassword::prompt_password(prompt)?),
+        Passphrase::File(path) => {
+            let content = fs::read_to_string(path)?;
+            Ok(content.lines().next().unwrap_or_default().to_string())
+        }
+    }
+}
+
+pub fn parse_private_key(value: &str) -> Result<SecretKey> {
+    let bytes = hex::decode(value.trim().trim_start_matches("0x")).map_err(|err| NovaxError::keystore(format!("invalid private key : {}", err)))?;
+    Ok(SecretKey::from_slice(&bytes)?)
+}
+
+fn entry_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// A keystore name must be a plain file name, so the keystore is written inside the keystore directory.
+fn check_keystore_name(name: &str) -> Result<()> {
+    let mut components = Path::new(name).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(file_name)), None) if file_name == name => Ok(()),
+        _ => Err(NovaxError::keystore(format!("invalid keystore name {:?}, expected a file name without any directory", name))),
+    }
+}
+
+fn resolve_keystore(keystore: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(keystore);
+    if path.is_file() {
+        return Ok(path);
+    }
+    let path = keystore_dir().join(keystore);
+    if path.is_file() {
+        return Ok(path);
+    }
+    Err(NovaxError::keystore(format!("no keystore file or keystore named {} found", keystore)))
+}
+
+fn read_keystore_address(path: &Path) -> Result<Option<Address>> {
+    let header: KeystoreHeader = serde_json::from_slice(&fs::read(path)?).map_err(|err| NovaxError::keystore(format!("invalid keystore {} : {}", path.display(), err)))?;
+    match header.address {
+        Some(address) => {
+            let bytes = hex::decode(address.trim_start_matches("0x")).unwrap_or_default();
+            if bytes.len() != 20 {
+                return Err(NovaxError::keystore(format!("invalid address in keystore {}", path.display())));
+            }
+            Ok(Some(Address::from_slice(&bytes)))
+        }
+        None => Ok(None),
+    }
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<String> {
+    // Standard streams are not key files, and reading stdin here would hang on a terminal.
+    if fd < 3 {
+        return Err(NovaxError::keystore(format!("file descriptor {} is a standard stream, pass the key on another one (e.g. 3<key.txt)", fd)));
+    }
+    // Opened again through /dev/fd rather than adopted, so the descriptor stays owned by whoever opened it.
+    fs::read_to_string(format!("/dev/fd/{}", fd)).map_err(|err| NovaxError::keystore(format!("cannot read the private key from file descriptor {} : {}", fd, err)))
+}
+
+#[cfg(not(unix))]
+fn read_fd(_fd: i32) -> Result<String> {
+    Err(NovaxError::keystore("reading the private key from a file descriptor is only supported on unix"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keystore_names_are_plain_file_names() {
+        for name in ["main", "0x19e13130738568a964f7c7eb5d11fdc72271ae0f", "my.wallet"] {
+            assert!(check_keystore_name(name).is_ok(), "{:?} was refused", name);
+        }
+        for name in ["", ".", "..", "../main", "a/b", "/tmp/main", "./main", "main/"] {
+            assert!(matches!(check_keystore_name(name), Err(NovaxError::Keystore(_))), "{:?} was accepted", name);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+//! Library behind NovaXCli, to manage NovaX planets from your own bots.
+//!
+//! [`client::NovaxClient`] is the entry point : it owns the connection to the C-Chain, the game, planet and
+//! resource contracts and the key signing the transactions.
+
+pub mod client;
+pub mod config;
+pub mod contracts;
+pub mod error;
+pub mod fees;
+pub mod gas_oracle;
+pub mod keystore;
+pub mod metadata;
+pub mod multicall;
+pub mod nonce;
+pub mod production;
+pub mod tx;
+pub mod utils;
+
+/// The examples of the README, compiled by `cargo test`.
+#[cfg(doctest)]
+#[doc = include_str!("../README.md")]
+pub struct ReadmeDoctests;
@@ -0,0 +1,162 @@
+//! Planet metadata (name, coordinate, structure levels), served as JSON at the URI returned by `tokenURI`.
+//!
+//! The metadata server is queried asynchronously, with a timeout and retries. The last response for each URI is kept
+//! in a cache file with its `ETag` / `Last-Modified`, so unchanged metadata is not downloaded again, and so a planet
+//! whose metadata cannot be fetched (server down, malformed JSON) falls back on what was last seen of it.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, HeaderName, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use serde::{Deserialize, Serialize};
+use crate::error::{NovaxError, Result};
+use crate::utils::ResponseApi;
+
+pub const DEFAULT_METADATA_TIMEOUT: Duration = Duration::from_secs(10);
+pub const DEFAULT_METADATA_RETRIES: u32 = 3;
+pub const MAX_METADATA_RETRIES: u32 = 10;
+/// Wait before the first retry, doubled before each of the next ones up to [`MAX_RETRY_BACKOFF`].
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Path of the metadata cache, `~/.cache/novaxcli/metadata.json` on Linux.
+pub fn default_cache_path() -> PathBuf {
+    dirs::cache_dir().unwrap_or_else(|| PathBuf::from(".")).join("novaxcli").join("metadata.json")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetadataSettings {
+    /// Timeout of each request, connection included
+    pub timeout: Duration,
+    /// Retries of a request that timed out, failed with a server error or returned malformed JSON, at most
+    /// [`MAX_METADATA_RETRIES`]
+    pub retries: u32,
+}
+
+impl Default for MetadataSettings {
+    fn default() -> Self {
+        MetadataSettings { timeout: DEFAULT_METADATA_TIMEOUT, retries: DEFAULT_METADATA_RETRIES }
+    }
+}
+
+/// Last response of the server for a URI. The raw body is kept, it is parsed again on each use.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct CachedMetadata {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    body: String,
+}
+
+/// Why a request failed, and whether it is worth sending it again.
+struct Failure {
+    error: NovaxError,
+    retry: bool,
+}
+
+/// Asynchronous client of the metadata server.
+#[derive(Debug)]
+pub struct MetadataClient {
+    http: reqwest::Client,
+    settings: MetadataSettings,
+    cache: Mutex<HashMap<String, CachedMetadata>>,
+    /// Where the cache is loaded from and saved to, `None` to keep it in memory only
+    cache_path: Option<PathBuf>,
+}
+
+impl MetadataClient {
+    pub fn new(settings: MetadataSettings, cache_path: Option<PathBuf>) -> Result<MetadataClient> {
+        let settings = MetadataSettings { retries: settings.retries.min(MAX_METADATA_RETRIES), ..settings };
+        let http = reqwest::Client::builder().timeout(settings.timeout).build()?;
+        // A missing or unreadable cache only means every metadata is downloaded again.
+        let cache = cache_path
+            .as_ref()
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|content| serde_json::from_slice(&content).ok())
+            .unwrap_or_default();
+        Ok(MetadataClient { http, settings, cache: Mutex::new(cache), cache_path })
+    }
+
+    /// Metadata at `uri`.
+    ///
+    /// Failed requests are retried `retries` times with an exponential backoff, unless the server answered with a
+    /// client error (4xx). When every attempt failed, the cached metadata of `uri` is used if there is some.
+    pub async fn fetch(&self, uri: &str) -> Result<ResponseApi> {
+        let cached = self.cache.lock().unwrap().get(uri).cloned();
+        let mut attempt = 0;
+        let failure = loop {
+            match self.request(uri, cached.as_ref()).await {
+                Ok(metadata) => return Ok(metadata),
+                Err(failure) if failure.retry && attempt < self.settings.retries => {
+                    tokio::time::sleep(RETRY_BACKOFF.saturating_mul(2u32.saturating_pow(attempt)).min(MAX_RETRY_BACKOFF)).await;
+                    attempt += 1;
+                }
+                Err(failure) => break failure.error,
+            }
+        };
+
+        match cached.map(|cached| parse(uri, &cached.body)) {
+            Some(Ok(metadata)) => {
+                // On stderr, not to break the JSON reports printed on stdout.
+                eprintln!("Metadata of {} could not be fetched, using the cached copy -- {}", uri, failure);
+                Ok(metadata)
+            }
+            _ => Err(failure),
+        }
+    }
+
+    /// Save the cache to its file, if any.
+    pub fn save_cache(&self) -> Result<()> {
+        let path = match &self.cache_path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let content = serde_json::to_vec(&*self.cache.lock().unwrap()).map_err(|err| NovaxError::Io(err.into()))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        Ok(std::fs::write(path, content)?)
+    }
+
+    /// One attempt : a conditional request when the metadata is cached, a 304 answer meaning it did not change.
+    async fn request(&self, uri: &str, cached: Option<&CachedMetadata>) -> std::result::Result<ResponseApi, Failure> {
+        let mut request = self.http.get(uri);
+        if let Some(cached) = cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header(IF_NONE_MATCH, etag.as_str());
+            }
+            if let Some(last_modified) = &cached.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified.as_str());
+            }
+        }
+
+        let response = request.send().await.map_err(|err| Failure { error: err.into(), retry: true })?;
+        let status = response.status();
+        if status == StatusCode::NOT_MODIFIED {
+            if let Some(cached) = cached {
+                return parse(uri, &cached.body).map_err(|error| Failure { error, retry: false });
+            }
+        }
+        if !status.is_success() {
+            let retry = status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS;
+            return Err(Failure { error: NovaxError::Metadata(format!("{} answered {}", uri, status)), retry });
+        }
+
+        let headers = response.headers().clone();
+        let body = response.text().await.map_err(|err| Failure { error: err.into(), retry: true })?;
+        // A truncated or garbled body is most likely a hiccup of the server, it is worth asking again.
+        let metadata = parse(uri, &body).map_err(|error| Failure { error, retry: true })?;
+        let cached = CachedMetadata { etag: header(&headers, ETAG), last_modified: header(&headers, LAST_MODIFIED), body };
+        self.cache.lock().unwrap().insert(uri.to_string(), cached);
+        Ok(metadata)
+    }
+}
+
+fn parse(uri: &str, body: &str) -> Result<ResponseApi> {
+    serde_json::from_str(body).map_err(|err| NovaxError::Metadata(format!("malformed metadata at {} : {}", uri, err)))
+}
+
+fn header(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers.get(name).and_then(|value| value.to_str().ok()).map(str::to_string)
+}
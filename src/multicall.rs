@@ -0,0 +1,308 @@
+//! Batching of the contract reads : many `eth_call`s sent in one round trip.
+//!
+//! The reads are aggregated in a single `aggregate3` call to a [Multicall3](https://www.multicall3.com) contract.
+//! When there is no such contract, or the node cannot run it, they are sent as one JSON-RPC batch request, and as
+//! individual calls when the node does not accept batches either.
+
+use web3::Web3;
+use web3::ethabi::{self, Address, Function, Token};
+use web3::futures::future::join_all;
+use web3::transports::{Batch, WebSocket};
+use web3::types::{Bytes, CallRequest};
+use crate::error::{NovaxError, Result};
+use crate::utils::{decode_revert_data, is_revert, revert_reason};
+
+/// Address of Multicall3, the same on every chain it is deployed on (Avalanche C-Chain included).
+pub const MULTICALL3_ADDRESS: &str = "0xcA11bde05977b3631167028862bE2a173976CA11";
+pub const MULTICALL3_ABI: &[u8] = include_bytes!("../abi/multicall3.abi");
+
+/// Most calls aggregated in one `aggregate3` call, or sent in one JSON-RPC batch.
+const MAX_BATCH_SIZE: usize = 200;
+
+/// A read-only contract call : where it goes, its calldata and how to decode its result.
+#[derive(Debug, Clone)]
+pub struct ContractRead {
+    pub to: Address,
+    pub data: Bytes,
+    function: Function,
+}
+
+impl ContractRead {
+    pub fn new(to: Address, function: &Function, tokens: &[Token]) -> ContractRead {
+        let data = function.encode_input(tokens).unwrap_or_else(|err| panic!("arguments of {} do not match the embedded ABI : {}", function.name, err));
+        ContractRead { to, data: Bytes::from(data), function: function.clone() }
+    }
+
+    /// Decode the data returned by the call.
+    pub fn decode(&self, output: &[u8]) -> Result<Vec<Token>> {
+        self.function.decode_output(output).map_err(|err| NovaxError::Abi(format!("cannot decode the result of {} : {}", self.function.name, err)))
+    }
+}
+
+/// Why a read of a batch failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ReadFailure {
+    /// The call reverted, with its reason when the contract gave one
+    Reverted(Option<String>),
+    /// The node could not run the call, or its result was not understood
+    Failed(String),
+}
+
+impl ReadFailure {
+    fn of(err: &web3::Error) -> ReadFailure {
+        if is_revert(err) {
+            ReadFailure::Reverted(revert_reason(err))
+        } else {
+            ReadFailure::Failed(err.to_string())
+        }
+    }
+}
+
+type ReadOutput = std::result::Result<Bytes, ReadFailure>;
+
+/// Sends batches of [`ContractRead`]s.
+#[derive(Debug, Clone)]
+pub struct Multicall {
+    web3: Web3<WebSocket>,
+    address: Option<Address>,
+    aggregate3: Function,
+}
+
+impl Multicall {
+    /// Batch the reads with the Multicall3 contract at `address`, or with JSON-RPC batch requests when `None`.
+    pub fn new(web3: Web3<WebSocket>, address: Option<Address>) -> Multicall {
+        let abi = ethabi::Contract::load(MULTICALL3_ABI).expect("embedded Multicall3 ABI is valid");
+        let aggregate3 = abi.function("aggregate3").expect("embedded Multicall3 ABI has aggregate3").clone();
+        Multicall { web3, address, aggregate3 }
+    }
+
+    /// Run the reads and decode their results, in the order of `reads`. The first read that fails fails them all.
+    ///
+    /// A read that reverted fails with a [`NovaxError::Abi`] holding its revert reason, one the node could not run
+    /// with a [`NovaxError::Rpc`].
+    pub async fn read(&self, reads: &[ContractRead]) -> Result<Vec<Vec<Token>>> {
+        let mut results = Vec::with_capacity(reads.len());
+        for chunk in reads.chunks(MAX_BATCH_SIZE) {
+            results.extend(decode_outputs(chunk, self.call(chunk).await?)?);
+        }
+        Ok(results)
+    }
+
+    /// Raw result of each read, or why it failed.
+    async fn call(&self, reads: &[ContractRead]) -> Result<Vec<ReadOutput>> {
+        if let Some(address) = self.address {
+            match self.aggregate(address, reads).await {
+                Ok(outputs) => return Ok(outputs),
+                Err(err) if !falls_back_on_batch(&err) => return Err(err),
+                Err(_) => {}
+            }
+        }
+        match self.batch(reads).await {
+            Ok(outputs) => Ok(outputs),
+            Err(err) if falls_back_one_by_one(&err) => Ok(self.one_by_one(reads).await),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// All the reads in one `aggregate3` call, each of them allowed to fail on its own.
+    async fn aggregate(&self, address: Address, reads: &[ContractRead]) -> Result<Vec<ReadOutput>> {
+        let data = encode_aggregate3(&self.aggregate3, reads)?;
+        let call = CallRequest { to: Some(address), data: Some(Bytes::from(data)), ..CallRequest::default() };
+        let output = self.web3.eth().call(call, None).await?;
+        decode_aggregate3(&self.aggregate3, &output.0, reads.len()).map_err(|err| NovaxError::Abi(format!("aggregate3 of {:?} : {}", address, err)))
+    }
+
+    /// All the reads as `eth_call`s of one JSON-RPC batch request.
+    async fn batch(&self, reads: &[ContractRead]) -> web3::Result<Vec<ReadOutput>> {
+        let batch = Web3::new(Batch::new(self.web3.transport().clone()));
+        // The calls are queued when created, the futures themselves are not needed : the batch returns every result.
+        let _queued: Vec<_> = reads.iter().map(|read| batch.eth().call(call_request(read), None)).collect();
+        let results = batch.transport().submit_batch().await?;
+        Ok(results
+            .into_iter()
+            .map(|result| match result {
+                Ok(value) => serde_json::from_value::<Bytes>(value).map_err(|err| ReadFailure::Failed(err.to_string())),
+                Err(err) => Err(ReadFailure::of(&err)),
+            })
+            .collect())
+    }
+
+    /// All the reads as individual `eth_call`s, sent at the same time.
+    async fn one_by_one(&self, reads: &[ContractRead]) -> Vec<ReadOutput> {
+        let calls = reads.iter().map(|read| self.web3.eth().call(call_request(read), None));
+        join_all(calls).await.into_iter().map(|result| result.map_err(|err| ReadFailure::of(&err))).collect()
+    }
+}
+
+fn call_request(read: &ContractRead) -> CallRequest {
+    CallRequest { to: Some(read.to), data: Some(read.data.clone()), ..CallRequest::default() }
+}
+
+/// Whether `aggregate3` failed because the contract is missing (on a fork, a testnet) or the node cannot run it, so
+/// the reads are worth sending as a batch instead.
+fn falls_back_on_batch(err: &NovaxError) -> bool {
+    matches!(err, NovaxError::Rpc(web3::Error::Rpc(_)) | NovaxError::Abi(_))
+}
+
+/// Whether a JSON-RPC batch failed because the node does not accept batches, so the reads are worth sending one by one.
+fn falls_back_one_by_one(err: &web3::Error) -> bool {
+    matches!(err, web3::Error::Rpc(_) | web3::Error::InvalidResponse(_) | web3::Error::Decoder(_))
+}
+
+/// Calldata of `aggregate3` running every read, each of them allowed to fail on its own.
+fn encode_aggregate3(aggregate3: &Function, reads: &[ContractRead]) -> Result<Vec<u8>> {
+    let calls = reads.iter().map(|read| Token::Tuple(vec![Token::Address(read.to), Token::Bool(true), Token::Bytes(read.data.0.clone())])).collect();
+    Ok(aggregate3.encode_input(&[Token::Array(calls)])?)
+}
+
+/// Result of each of the `count` calls from the data returned by `aggregate3`. The data of a failed call is its
+/// revert data.
+fn decode_aggregate3(aggregate3: &Function, output: &[u8], count: usize) -> std::result::Result<Vec<ReadOutput>, String> {
+    let results = match aggregate3.decode_output(output).map_err(|err| err.to_string())?.pop() {
+        Some(Token::Array(results)) if results.len() == count => results,
+        _ => return Err("it did not return one result per call".to_string()),
+    };
+    Ok(results
+        .into_iter()
+        .map(|result| match result {
+            Token::Tuple(fields) => match fields.as_slice() {
+                [Token::Bool(true), Token::Bytes(output)] => Ok(Bytes::from(output.clone())),
+                [Token::Bool(false), Token::Bytes(output)] => Err(ReadFailure::Reverted(decode_revert_data(&hex::encode(output)))),
+                _ => Err(ReadFailure::Failed("unexpected aggregate3 result".to_string())),
+            },
+            _ => Err(ReadFailure::Failed("unexpected aggregate3 result".to_string())),
+        })
+        .collect())
+}
+
+/// Decoded result of each read, or the error of the first one that failed.
+fn decode_outputs(reads: &[ContractRead], outputs: Vec<ReadOutput>) -> Result<Vec<Vec<Token>>> {
+    reads
+        .iter()
+        .zip(outputs)
+        .map(|(read, output)| match output {
+            Ok(output) => read.decode(&output.0),
+            Err(ReadFailure::Reverted(reason)) => {
+                let reason = reason.map(|reason| format!(" : {}", reason)).unwrap_or_default();
+                Err(NovaxError::Abi(format!("{} on {:?} reverted{}", read.function.name, read.to, reason)))
+            }
+            Err(ReadFailure::Failed(reason)) => {
+                Err(NovaxError::Rpc(web3::Error::InvalidResponse(format!("{} on {:?} failed : {}", read.function.name, read.to, reason))))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use web3::ethabi::ethereum_types::U256;
+    use crate::contracts::NOVAX_GAME_ABI;
+    use super::*;
+
+    fn aggregate3() -> Function {
+        ethabi::Contract::load(MULTICALL3_ABI).unwrap().function("aggregate3").unwrap().clone()
+    }
+
+    fn resource_amount_read(planet_no: u64) -> ContractRead {
+        let game = ethabi::Contract::load(NOVAX_GAME_ABI).unwrap();
+        ContractRead::new(Address::from_low_u64_be(7), game.function("getResourceAmount").unwrap(), &[Token::Uint(U256::one()), Token::Uint(U256::from(planet_no))])
+    }
+
+    fn word(value: u64) -> Vec<u8> {
+        let mut word = vec![0; 24];
+        word.extend_from_slice(&value.to_be_bytes());
+        word
+    }
+
+    fn padded(bytes: &[u8]) -> Vec<u8> {
+        let mut bytes = bytes.to_vec();
+        bytes.resize(bytes.len().div_ceil(32) * 32, 0);
+        bytes
+    }
+
+    /// `Error("nope")`, as returned by a `require(false, "nope")`.
+    fn revert_data() -> Vec<u8> {
+        [vec![0x08, 0xc3, 0x79, 0xa0], word(0x20), word(4), padded(b"nope")].concat()
+    }
+
+    /// `aggregate3` return data for a call returning 42 and a call that reverted with `Error("nope")`.
+    fn aggregate3_output() -> Vec<u8> {
+        let revert = revert_data();
+        let success = [word(1), word(0x40), word(32), word(42)].concat();
+        let failure = [word(0), word(0x40), word(revert.len() as u64), padded(&revert)].concat();
+        [word(0x20), word(2), word(0x40), word(0x40 + success.len() as u64), success, failure].concat()
+    }
+
+    fn rpc_error(message: &str) -> web3::Error {
+        web3::Error::Rpc(serde_json::from_value(json!({ "code": -32000, "message": message })).unwrap())
+    }
+
+    #[test]
+    fn aggregate3_allows_every_call_to_fail() {
+        let reads = [resource_amount_read(1), resource_amount_read(2)];
+        let data = encode_aggregate3(&aggregate3(), &reads).unwrap();
+        assert_eq!(data[..4], aggregate3().short_signature());
+
+        let calls = match aggregate3().decode_input(&data[4..]).unwrap().pop() {
+            Some(Token::Array(calls)) => calls,
+            tokens => panic!("unexpected aggregate3 input {:?}", tokens),
+        };
+        assert_eq!(calls.len(), 2);
+        for (call, read) in calls.iter().zip(&reads) {
+            assert_eq!(call, &Token::Tuple(vec![Token::Address(read.to), Token::Bool(true), Token::Bytes(read.data.0.clone())]));
+        }
+    }
+
+    #[test]
+    fn aggregate3_results_are_decoded_in_order() {
+        let outputs = decode_aggregate3(&aggregate3(), &aggregate3_output(), 2).unwrap();
+        assert_eq!(outputs, vec![Ok(Bytes::from(word(42))), Err(ReadFailure::Reverted(Some("nope".to_string())))]);
+    }
+
+    #[test]
+    fn aggregate3_without_one_result_per_call_is_rejected() {
+        assert!(decode_aggregate3(&aggregate3(), &aggregate3_output(), 3).is_err());
+        assert!(decode_aggregate3(&aggregate3(), &word(0x20), 2).is_err());
+    }
+
+    #[test]
+    fn failed_call_without_a_reason_is_a_revert() {
+        let output = [word(0x20), word(1), word(0x20), word(0), word(0x40), word(0)].concat();
+        assert_eq!(decode_aggregate3(&aggregate3(), &output, 1).unwrap(), vec![Err(ReadFailure::Reverted(None))]);
+    }
+
+    #[test]
+    fn reverted_reads_fail_with_their_revert_reason() {
+        let reads = [resource_amount_read(1), resource_amount_read(2)];
+        let outputs = decode_aggregate3(&aggregate3(), &aggregate3_output(), 2).unwrap();
+        match decode_outputs(&reads, outputs) {
+            Err(NovaxError::Abi(message)) => assert!(message.contains("getResourceAmount") && message.ends_with("reverted : nope"), "{}", message),
+            result => panic!("unexpected result {:?}", result),
+        }
+
+        let outputs = vec![Ok(Bytes::from(word(42))), Ok(Bytes::from(word(5)))];
+        assert_eq!(decode_outputs(&reads, outputs).unwrap(), vec![vec![Token::Uint(U256::from(42))], vec![Token::Uint(U256::from(5))]]);
+
+        let outputs = vec![Ok(Bytes::from(word(42))), Err(ReadFailure::Failed("header not found".to_string()))];
+        assert!(matches!(decode_outputs(&reads, outputs), Err(NovaxError::Rpc(web3::Error::InvalidResponse(_)))));
+    }
+
+    #[test]
+    fn node_errors_are_told_apart_from_reverts() {
+        assert_eq!(ReadFailure::of(&rpc_error("execution reverted: nope")), ReadFailure::Reverted(Some("nope".to_string())));
+        assert_eq!(ReadFailure::of(&rpc_error("execution reverted")), ReadFailure::Reverted(None));
+        assert!(matches!(ReadFailure::of(&rpc_error("header not found")), ReadFailure::Failed(_)));
+    }
+
+    #[test]
+    fn unusable_aggregate3_falls_back_on_a_batch_then_on_single_calls() {
+        assert!(falls_back_on_batch(&NovaxError::Rpc(rpc_error("execution reverted"))));
+        assert!(falls_back_on_batch(&NovaxError::Abi("it did not return one result per call".to_string())));
+        assert!(!falls_back_on_batch(&NovaxError::Rpc(web3::Error::Unreachable)));
+
+        assert!(falls_back_one_by_one(&rpc_error("batch requests are not supported")));
+        assert!(falls_back_one_by_one(&web3::Error::InvalidResponse("not a batch response".to_string())));
+        assert!(!falls_back_one_by_one(&web3::Error::Unreachable));
+    }
+}
@@ -0,0 +1,45 @@
+//! Local allocation of the nonces, so several transactions of a wallet can be in flight at once.
+
+use tokio::sync::Mutex;
+use web3::Web3;
+use web3::ethabi::Address;
+use web3::ethabi::ethereum_types::U256;
+use web3::transports::WebSocket;
+use crate::error::Result;
+use crate::utils::get_current_nonce;
+
+/// Hands out sequential nonces without asking the node each time.
+///
+/// The first reservation starts from the pending transaction count of the wallet. When a transaction could not be
+/// sent, or got dropped, [`NonceManager::resync`] goes back to the count of the node.
+#[derive(Debug)]
+pub struct NonceManager {
+    web3: Web3<WebSocket>,
+    address: Address,
+    next: Mutex<Option<U256>>,
+}
+
+impl NonceManager {
+    pub fn new(web3: Web3<WebSocket>, address: Address) -> Self {
+        NonceManager { web3, address, next: Mutex::new(None) }
+    }
+
+    /// Reserve the next nonce of the wallet.
+    pub async fn reserve(&self) -> Result<U256> {
+        let mut next = self.next.lock().await;
+        let nonce = match *next {
+            Some(nonce) => nonce,
+            None => U256::from(get_current_nonce(self.address, &self.web3).await?),
+        };
+        *next = Some(nonce + 1);
+        Ok(nonce)
+    }
+
+    /// Drop the local count and start again from the pending transaction count of the node.
+    pub async fn resync(&self) -> Result<U256> {
+        let mut next = self.next.lock().await;
+        let nonce = U256::from(get_current_nonce(self.address, &self.web3).await?);
+        *next = Some(nonce);
+        Ok(nonce)
+    }
+}
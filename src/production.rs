@@ -0,0 +1,158 @@
+//! Production model of the planets : how many resource tokens each structure produces, by level.
+
+use std::ops::Add;
+use serde::Serialize;
+use crate::contracts::{Resource, Structure};
+use crate::utils::ResponseApi;
+
+/// Production of each structure, in tokens per second, by level.
+///
+/// `rates[0]` is the production at level 1, `rates[1]` at level 2 and so on. Above the last level of the table the
+/// production keeps growing linearly with the level, so a table of a single rate is a production proportional to the
+/// level. A structure at level 0 (not built) produces nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductionTable {
+    pub solar: Vec<f64>,
+    pub metal: Vec<f64>,
+    pub crystal: Vec<f64>,
+}
+
+/// Production of a planet, or of several planets, in tokens per second.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ProductionRates {
+    pub solar: f64,
+    pub metal: f64,
+    pub crystal: f64,
+}
+
+impl Default for ProductionTable {
+    /// Rates of the game at launch : 0.001 solar, 0.002 metal and 0.0001 crystal per second and per level.
+    fn default() -> Self {
+        ProductionTable { solar: vec![0.001], metal: vec![0.002], crystal: vec![0.0001] }
+    }
+}
+
+impl ProductionTable {
+    /// Rates of the structure producing `resource`, by level.
+    pub fn rates(&self, resource: Resource) -> &[f64] {
+        match resource {
+            Resource::Solar => &self.solar,
+            Resource::Metal => &self.metal,
+            Resource::Crystal => &self.crystal,
+        }
+    }
+
+    /// Tokens per second produced by `structure` at `level`.
+    pub fn rate(&self, structure: Structure, level: u32) -> f64 {
+        let rates = self.rates(structure.resource());
+        match (level as usize).checked_sub(1) {
+            None => 0.,
+            Some(index) => match rates.get(index) {
+                Some(rate) => *rate,
+                None => rates.last().map_or(0., |last| last * level as f64 / rates.len() as f64),
+            },
+        }
+    }
+
+    /// Production of a planet, from the levels of its structures.
+    pub fn planet_rates(&self, planet: &ResponseApi) -> ProductionRates {
+        ProductionRates {
+            solar: self.rate(Structure::Solar, planet.structure_level(Structure::Solar)),
+            metal: self.rate(Structure::Mine, planet.structure_level(Structure::Mine)),
+            crystal: self.rate(Structure::Crystal, planet.structure_level(Structure::Crystal)),
+        }
+    }
+
+    /// Check that every structure has at least one rate, and no negative one.
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        for resource in Resource::ALL {
+            let rates = self.rates(resource);
+            if rates.is_empty() {
+                return Err(format!("no production rate for {}", resource));
+            }
+            if rates.iter().any(|rate| !rate.is_finite() || *rate < 0.) {
+                return Err(format!("invalid production rate for {}, rates must be positive numbers", resource));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ProductionRates {
+    pub fn get(&self, resource: Resource) -> f64 {
+        match resource {
+            Resource::Solar => self.solar,
+            Resource::Metal => self.metal,
+            Resource::Crystal => self.crystal,
+        }
+    }
+
+    /// Tokens produced in `seconds`.
+    pub fn over(&self, seconds: f64) -> ProductionRates {
+        ProductionRates { solar: self.solar * seconds, metal: self.metal * seconds, crystal: self.crystal * seconds }
+    }
+}
+
+impl Add for ProductionRates {
+    type Output = ProductionRates;
+
+    fn add(self, other: ProductionRates) -> ProductionRates {
+        ProductionRates {
+            solar: self.solar + other.solar,
+            metal: self.metal + other.metal,
+            crystal: self.crystal + other.crystal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> ProductionTable {
+        ProductionTable { solar: vec![1., 3., 6.], metal: vec![0.5], crystal: vec![2.] }
+    }
+
+    #[test]
+    fn rate_reads_the_table_by_level() {
+        let table = table();
+        assert_eq!(table.rate(Structure::Solar, 1), 1.);
+        assert_eq!(table.rate(Structure::Solar, 3), 6.);
+        assert_eq!(table.rate(Structure::Mine, 1), 0.5);
+    }
+
+    #[test]
+    fn rate_is_zero_at_level_zero() {
+        let table = table();
+        for structure in Structure::ALL {
+            assert_eq!(table.rate(structure, 0), 0.);
+        }
+    }
+
+    #[test]
+    fn rate_extrapolates_linearly_past_the_table() {
+        let table = table();
+        // 6 at level 3, so 2 per level above it.
+        assert_eq!(table.rate(Structure::Solar, 4), 8.);
+        assert_eq!(table.rate(Structure::Solar, 6), 12.);
+        // A single rate is a production proportional to the level.
+        assert_eq!(table.rate(Structure::Mine, 4), 2.);
+        assert_eq!(table.rate(Structure::Crystal, 10), 20.);
+    }
+
+    #[test]
+    fn validate_accepts_the_default_table() {
+        assert_eq!(ProductionTable::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_rejects_invalid_tables() {
+        let empty = ProductionTable { metal: Vec::new(), ..table() };
+        assert_eq!(empty.validate(), Err("no production rate for metal".to_string()));
+
+        for rate in [-1., f64::NAN, f64::INFINITY] {
+            let invalid = ProductionTable { crystal: vec![1., rate], ..table() };
+            assert!(invalid.validate().is_err(), "rate {} was accepted", rate);
+        }
+    }
+}
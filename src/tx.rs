@@ -0,0 +1,554 @@
+//! Transaction pipeline shared by every write operation : build, estimate, sign, send, wait for the receipt and
+//! decode its logs.
+
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use secp256k1::SecretKey;
+use web3::Web3;
+use web3::ethabi::{self, Address, LogParam, RawLog, Token};
+use web3::ethabi::ethereum_types::{U256, U64};
+use web3::transports::WebSocket;
+use web3::types::{BlockId, BlockNumber, Bytes, CallRequest, TransactionId, TransactionParameters, TransactionReceipt, H256};
+use crate::error::{NovaxError, Result};
+use crate::fees::{FeePolicy, GasFees};
+use crate::keystore::address_of;
+use crate::nonce::NonceManager;
+use crate::utils::{get_gas_usage_estimation, revert_reason};
+
+/// How many times a transaction dropped by the node is sent again before giving up.
+const MAX_RESUBMISSIONS: u32 = 3;
+/// Gas limit of a plain AVAX transfer, used by the cancellations.
+const TRANSFER_GAS: u64 = 21_000;
+
+/// How long to wait for a transaction once it has been sent, and what to do when it is stuck.
+#[derive(Debug, Clone, Copy)]
+pub struct TxSettings {
+    /// Number of blocks (including the one holding the transaction) to wait for before it is considered final
+    pub confirmations: u64,
+    /// How long to wait for the receipt and the confirmations before giving up
+    pub receipt_timeout: Duration,
+    /// Delay between two receipt polls
+    pub poll_interval: Duration,
+    /// What to do with a transaction still not mined after `stuck_timeout`
+    pub stuck_policy: StuckPolicy,
+    pub stuck_timeout: Duration,
+    /// Fee increase of a replacement transaction, in percent (nodes require at least 10)
+    pub fee_bump: u64,
+}
+
+impl Default for TxSettings {
+    fn default() -> Self {
+        TxSettings {
+            confirmations: 1,
+            receipt_timeout: Duration::from_secs(300),
+            poll_interval: Duration::from_secs(3),
+            stuck_policy: StuckPolicy::Wait,
+            stuck_timeout: Duration::from_secs(60),
+            fee_bump: 15,
+        }
+    }
+}
+
+/// What to do with a transaction that is not mined in time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StuckPolicy {
+    /// Keep waiting until the receipt timeout
+    Wait,
+    /// Send the same transaction again with the same nonce and bumped fees
+    SpeedUp,
+    /// Replace it by a zero-value transfer to the wallet itself with the same nonce and bumped fees
+    Cancel,
+}
+
+impl FromStr for StuckPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value {
+            "wait" => Ok(StuckPolicy::Wait),
+            "speed-up" => Ok(StuckPolicy::SpeedUp),
+            "cancel" => Ok(StuckPolicy::Cancel),
+            _ => Err(format!("unknown stuck transaction policy {}, expected wait, speed-up or cancel", value)),
+        }
+    }
+}
+
+/// A contract call to send as a transaction.
+#[derive(Debug, Clone)]
+pub struct TxRequest {
+    pub to: Address,
+    pub data: Bytes,
+    /// AVAX attached to the call, in wei
+    pub value: U256,
+    /// Short description of the transaction, printed while waiting for it
+    pub label: String,
+}
+
+impl TxRequest {
+    pub fn new(to: Address, data: Bytes, label: impl Into<String>) -> Self {
+        TxRequest { to, data, value: U256::zero(), label: label.into() }
+    }
+
+    pub fn with_value(mut self, value: U256) -> Self {
+        self.value = value;
+        self
+    }
+}
+
+/// An event of the receipt, decoded with the ABI of the contract that emitted it.
+#[derive(Debug, Clone)]
+pub struct DecodedEvent {
+    pub address: Address,
+    pub name: String,
+    pub params: Vec<LogParam>,
+}
+
+impl DecodedEvent {
+    /// Value of the parameter `name` of the event.
+    pub fn param(&self, name: &str) -> Option<&Token> {
+        self.params.iter().find(|param| param.name == name).map(|param| &param.value)
+    }
+}
+
+/// A mined and successful transaction.
+#[derive(Debug, Clone)]
+pub struct TxOutcome {
+    pub receipt: TransactionReceipt,
+    /// Events emitted by the known contracts; logs of other contracts are left out
+    pub events: Vec<DecodedEvent>,
+}
+
+/// Fee paid by a mined transaction, in wei, when the node reports its effective gas price.
+pub fn receipt_fee(receipt: &TransactionReceipt) -> Option<U256> {
+    Some(receipt.gas_used?.saturating_mul(receipt.effective_gas_price?))
+}
+
+/// What a transaction would do if it was sent now, see [`simulate`].
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub from: Address,
+    pub request: TxRequest,
+    /// Result of the `eth_call` of the transaction on the latest state
+    pub call: CallOutcome,
+    /// Estimated gas usage, or why it could not be estimated
+    pub gas: std::result::Result<U256, String>,
+    pub fees: GasFees,
+}
+
+#[derive(Debug, Clone)]
+pub enum CallOutcome {
+    /// The call succeeded and returned this data
+    Success(Bytes),
+    /// The call reverted, with its reason when the contract gave one
+    Reverted(Option<String>),
+}
+
+impl Simulation {
+    /// Highest fee the transaction may cost, in wei.
+    pub fn max_fee(&self) -> Option<U256> {
+        self.gas.as_ref().ok().map(|gas| gas.saturating_mul(self.fees.max_price()))
+    }
+}
+
+/// Run `request` with `eth_call` and estimate its gas and fees, without signing or sending anything.
+pub async fn simulate(web3: &Web3<WebSocket>, from: Address, request: &TxRequest, fees: &FeePolicy) -> Result<Simulation> {
+    let fees = fees.fees(web3).await?;
+    let mut call = CallRequest {
+        from: Some(from),
+        to: Some(request.to),
+        value: Some(request.value),
+        data: Some(request.data.clone()),
+        ..CallRequest::default()
+    };
+    fees.apply_to_call(&mut call);
+
+    let outcome = match web3.eth().call(call.clone(), None).await {
+        Ok(data) => CallOutcome::Success(data),
+        Err(err @ web3::Error::Rpc(_)) => CallOutcome::Reverted(revert_reason(&err)),
+        Err(err) => return Err(err.into()),
+    };
+    let gas = match &outcome {
+        CallOutcome::Success(_) => web3.eth().estimate_gas(call, None).await.map_err(|err| err.to_string()),
+        CallOutcome::Reverted(_) => Err("the call reverts".to_string()),
+    };
+    Ok(Simulation { from, request: request.clone(), call: outcome, gas, fees })
+}
+
+/// A transaction handed over to the node and not confirmed yet.
+#[derive(Debug, Clone)]
+pub struct PendingTx {
+    pub hash: H256,
+    pub nonce: U256,
+    pub request: TxRequest,
+    pub gas: U256,
+    pub fees: GasFees,
+}
+
+/// A transaction replacing a pending one of the wallet, see [`TxSender::speed_up`] and [`TxSender::cancel`].
+#[derive(Debug, Clone)]
+pub struct Replacement {
+    pub nonce: U256,
+    pub request: TxRequest,
+    pub gas: U256,
+    pub fees: GasFees,
+}
+
+enum ReceiptPoll {
+    /// Mined with the configured number of confirmations
+    Mined(Box<TransactionReceipt>),
+    /// Mined, waiting for more confirmations
+    Confirming,
+    Dropped,
+    Pending,
+}
+
+/// Signs and sends transactions for one key, then follows them until they are confirmed.
+///
+/// The nonces are reserved locally by a [`NonceManager`] shared by the clones of the sender, so transactions can be
+/// submitted back to back without waiting for the previous ones to be mined.
+#[derive(Clone)]
+pub struct TxSender {
+    web3: Web3<WebSocket>,
+    chain_id: u64,
+    signer: SecretKey,
+    settings: TxSettings,
+    nonces: Arc<NonceManager>,
+    abis: Vec<(Address, ethabi::Contract)>,
+}
+
+impl TxSender {
+    pub fn new(web3: Web3<WebSocket>, chain_id: u64, signer: SecretKey) -> Self {
+        let nonces = Arc::new(NonceManager::new(web3.clone(), address_of(&signer)));
+        TxSender { web3, chain_id, signer, settings: TxSettings::default(), nonces, abis: Vec::new() }
+    }
+
+    pub fn with_settings(mut self, settings: TxSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    /// Decode the logs emitted by the contract at `address` with `abi`.
+    pub fn with_abi(mut self, address: Address, abi: ethabi::Contract) -> Self {
+        self.abis.push((address, abi));
+        self
+    }
+
+    pub fn address(&self) -> Address {
+        address_of(&self.signer)
+    }
+
+    pub fn settings(&self) -> &TxSettings {
+        &self.settings
+    }
+
+    pub fn nonces(&self) -> &NonceManager {
+        &self.nonces
+    }
+
+    /// Run the whole pipeline for `request` and return the receipt once the transaction is confirmed.
+    pub async fn send(&self, request: &TxRequest, fees: &FeePolicy) -> Result<TxOutcome> {
+        let pending = self.submit(request, fees).await?;
+        self.confirm(pending, fees).await
+    }
+
+    /// Submit all the requests back to back with sequential nonces, then wait for each of them.
+    ///
+    /// A request that cannot be submitted or confirmed does not stop the others, its error takes its place in the
+    /// returned list. The nonces are resynced with the node once the batch is over, and as soon as a transaction is
+    /// dropped or times out without its nonce being used.
+    pub async fn send_batch(&self, requests: &[TxRequest], fees: &FeePolicy) -> Vec<Result<TxOutcome>> {
+        let mut submitted = Vec::new();
+        for request in requests {
+            submitted.push(self.submit(request, fees).await);
+        }
+
+        let mut outcomes = Vec::new();
+        for pending in submitted {
+            outcomes.push(match pending {
+                Ok(pending) => self.confirm(pending, fees).await,
+                Err(err) => Err(err),
+            });
+        }
+        if let Err(err) = self.nonces.resync().await {
+            println!("Could not resync the nonce of {:?} -- {}", self.address(), err);
+        }
+        outcomes
+    }
+
+    /// Estimate, sign and send `request` with the next nonce, without waiting for it to be mined.
+    pub async fn submit(&self, request: &TxRequest, fees: &FeePolicy) -> Result<PendingTx> {
+        let fees = fees.fees(&self.web3).await?;
+        let gas = get_gas_usage_estimation(self.address(), &fees, &self.web3, request.to, &request.data, request.value).await?;
+        let nonce = self.nonces.reserve().await?;
+        match self.broadcast(request, nonce, gas, fees).await {
+            Ok(hash) => Ok(PendingTx { hash, nonce, request: request.clone(), gas, fees }),
+            Err(err) => {
+                // The reserved nonce was not used, the next transactions would be stuck behind the gap.
+                self.nonces.resync().await?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Wait for a submitted transaction to be confirmed, and decode its logs.
+    ///
+    /// A transaction dropped by the node is sent again with the same nonce, so it does not leave a gap blocking the
+    /// next ones. One still pending after the stuck timeout is sped up or cancelled according to the stuck policy,
+    /// the fees of the replacements never going above the max fee cap of `fees`.
+    /// Fails with [`NovaxError::TxDropped`] when the nonce ends up used by another transaction, a cancellation
+    /// included. When the transaction is dropped or times out and its nonce is still unused, the nonces are resynced
+    /// with the node, so the next transactions do not queue up behind the gap.
+    pub async fn confirm(&self, pending: PendingTx, fees: &FeePolicy) -> Result<TxOutcome> {
+        let nonce = pending.nonce;
+        let outcome = self.follow(pending, fees).await;
+        if let Err(NovaxError::TxDropped { .. }) | Err(NovaxError::ReceiptTimeout { .. }) = &outcome {
+            if let Err(err) = self.resync_if_unused(nonce).await {
+                println!("Could not resync the nonce of {:?} -- {}", self.address(), err);
+            }
+        }
+        outcome
+    }
+
+    /// Resync the nonces with the node when no mined transaction of the wallet uses `nonce`.
+    async fn resync_if_unused(&self, nonce: U256) -> Result<()> {
+        let mined_nonce = self.web3.eth().transaction_count(self.address(), Some(BlockNumber::Latest)).await?;
+        if mined_nonce <= nonce {
+            self.nonces.resync().await?;
+        }
+        Ok(())
+    }
+
+    /// [`TxSender::confirm`], without the resync of the nonces.
+    async fn follow(&self, mut pending: PendingTx, fees: &FeePolicy) -> Result<TxOutcome> {
+        let started = Instant::now();
+        let original = pending.clone();
+        // Every transaction sent with this nonce, any of them may be the one getting mined.
+        let mut sent = vec![pending.hash];
+        let mut cancellations = Vec::new();
+        let mut resubmissions = 0;
+        let receipt = loop {
+            let remaining = self.settings.receipt_timeout.saturating_sub(started.elapsed());
+            let wait = match self.settings.stuck_policy {
+                StuckPolicy::Wait => remaining,
+                StuckPolicy::SpeedUp | StuckPolicy::Cancel => remaining.min(self.settings.stuck_timeout),
+            };
+            match self.poll_receipt(&sent, &pending.request.label, wait).await? {
+                ReceiptPoll::Mined(receipt) => break *receipt,
+                ReceiptPoll::Confirming if started.elapsed() >= self.settings.receipt_timeout => {
+                    return Err(NovaxError::ReceiptTimeout { tx_hash: pending.hash, timeout: self.settings.receipt_timeout });
+                }
+                // Mined : nothing to replace anymore, only the confirmations are waited for.
+                ReceiptPoll::Confirming => tokio::time::sleep(self.settings.poll_interval).await,
+                ReceiptPoll::Dropped => {
+                    let mined_nonce = self.web3.eth().transaction_count(self.address(), Some(BlockNumber::Latest)).await?;
+                    if mined_nonce > pending.nonce || resubmissions >= MAX_RESUBMISSIONS {
+                        return Err(NovaxError::TxDropped { tx_hash: pending.hash, nonce: pending.nonce });
+                    }
+                    resubmissions += 1;
+                    println!("{:?} -- {} -- {:?} was dropped, sending it again with nonce {}", Instant::now(), pending.request.label, pending.hash, pending.nonce);
+                    let fresh = fees.fees(&self.web3).await?;
+                    let fees = pending.fees.at_least(&fresh);
+                    match self.broadcast(&pending.request, pending.nonce, pending.gas, fees).await {
+                        Ok(hash) => {
+                            pending.fees = fees;
+                            pending.hash = hash;
+                            sent.push(hash);
+                        }
+                        // The node still has a transaction with this nonce after all : keep waiting for it.
+                        Err(err) if is_still_pending(&err) => println!("{:?} -- {} -- {:?} is still known by the node -- {}", Instant::now(), pending.request.label, pending.hash, err),
+                        Err(err) => return Err(err),
+                    }
+                }
+                ReceiptPoll::Pending if started.elapsed() >= self.settings.receipt_timeout => {
+                    return Err(NovaxError::ReceiptTimeout { tx_hash: pending.hash, timeout: self.settings.receipt_timeout });
+                }
+                ReceiptPoll::Pending => {
+                    let mut bumped = pending.fees.bumped(self.settings.fee_bump);
+                    if let Some(cap) = fees.max_fee_cap() {
+                        bumped = bumped.capped(cap);
+                        // A replacement could not pay more than the transaction already does : keep waiting for it.
+                        if bumped.max_price() <= pending.fees.max_price() {
+                            println!("{:?} -- {} -- {:?} is stuck but already pays the ceiling of {} wei, waiting", Instant::now(), pending.request.label, pending.hash, cap);
+                            continue;
+                        }
+                    }
+                    let mut replacement = Replacement { nonce: pending.nonce, request: pending.request.clone(), gas: pending.gas, fees: bumped };
+                    if self.settings.stuck_policy == StuckPolicy::Cancel && cancellations.is_empty() {
+                        replacement.request = self.cancel_request(pending.nonce);
+                        replacement.gas = U256::from(TRANSFER_GAS);
+                    }
+                    println!("{:?} -- {} -- {:?} is stuck, replacing it : {} with {}", Instant::now(), pending.request.label, pending.hash, replacement.request.label, replacement.fees);
+                    match self.send_replacement(&replacement).await {
+                        Ok(replaced) => {
+                            if self.settings.stuck_policy == StuckPolicy::Cancel {
+                                cancellations.push(replaced.hash);
+                            }
+                            sent.push(replaced.hash);
+                            pending = replaced;
+                        }
+                        // e.g. `replacement transaction underpriced`, the previous transactions are still valid.
+                        Err(err) => println!("{:?} -- {} -- replacement failed -- {}", Instant::now(), pending.request.label, err),
+                    }
+                }
+            }
+        };
+
+        if cancellations.contains(&receipt.transaction_hash) {
+            return Err(NovaxError::TxDropped { tx_hash: original.hash, nonce: original.nonce });
+        }
+        if receipt.status == Some(U64::from(0)) {
+            let reason = self.replay_revert_reason(&original.request, &receipt).await;
+            return Err(NovaxError::Reverted { tx_hash: receipt.transaction_hash, reason, receipt: Box::new(receipt) });
+        }
+        let events = self.decode_events(&receipt);
+        Ok(TxOutcome { receipt, events })
+    }
+
+    /// Same transaction as the pending `tx_hash` of the wallet, with fees bumped by the fee bump of the settings.
+    pub async fn speed_up(&self, tx_hash: H256) -> Result<Replacement> {
+        let transaction = self.web3.eth().transaction(TransactionId::Hash(tx_hash)).await?
+            .ok_or_else(|| NovaxError::config(format!("transaction {:?} not found", tx_hash)))?;
+        if transaction.from != Some(self.address()) {
+            return Err(NovaxError::config(format!("transaction {:?} was not sent by {:?}", tx_hash, self.address())));
+        }
+        if let Some(block_number) = transaction.block_number {
+            return Err(NovaxError::config(format!("transaction {:?} is already mined in block {}", tx_hash, block_number)));
+        }
+        let to = transaction.to.ok_or_else(|| NovaxError::config(format!("transaction {:?} deploys a contract, it cannot be sped up", tx_hash)))?;
+
+        let fees = match (transaction.max_fee_per_gas, transaction.max_priority_fee_per_gas) {
+            (Some(max_fee_per_gas), Some(max_priority_fee_per_gas)) => GasFees::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas },
+            _ => GasFees::Legacy { gas_price: transaction.gas_price.unwrap_or_default() },
+        };
+        Ok(Replacement {
+            nonce: transaction.nonce,
+            request: TxRequest::new(to, transaction.input, format!("Speed up of {:?}", tx_hash)).with_value(transaction.value),
+            gas: transaction.gas,
+            fees: fees.bumped(self.settings.fee_bump),
+        })
+    }
+
+    /// Zero-value transfer to the wallet itself replacing its pending transaction with `nonce`.
+    ///
+    /// The fees of the replaced transaction are unknown, the ones of `fees` are bumped by the fee bump of the settings.
+    pub async fn cancel(&self, nonce: U256, fees: &FeePolicy) -> Result<Replacement> {
+        let mined_nonce = self.web3.eth().transaction_count(self.address(), Some(BlockNumber::Latest)).await?;
+        if nonce < mined_nonce {
+            return Err(NovaxError::config(format!("nonce {} of {:?} is already used by a mined transaction", nonce, self.address())));
+        }
+        let fees = fees.fees(&self.web3).await?;
+        Ok(Replacement { nonce, request: self.cancel_request(nonce), gas: U256::from(TRANSFER_GAS), fees: fees.bumped(self.settings.fee_bump) })
+    }
+
+    /// Sign and send a replacement transaction.
+    pub async fn send_replacement(&self, replacement: &Replacement) -> Result<PendingTx> {
+        let hash = self.broadcast(&replacement.request, replacement.nonce, replacement.gas, replacement.fees).await?;
+        Ok(PendingTx { hash, nonce: replacement.nonce, request: replacement.request.clone(), gas: replacement.gas, fees: replacement.fees })
+    }
+
+    /// Sign `request` with the given nonce, gas limit and fees, and hand it over to the node, returning its hash.
+    pub async fn broadcast(&self, request: &TxRequest, nonce: U256, gas: U256, fees: GasFees) -> Result<H256> {
+        let mut transaction = TransactionParameters {
+            nonce: Some(nonce),
+            to: Some(request.to),
+            value: request.value,
+            gas,
+            data: request.data.clone(),
+            chain_id: Some(self.chain_id),
+            ..TransactionParameters::default()
+        };
+        fees.apply_to_transaction(&mut transaction);
+        let signed_tx = self.web3.accounts().sign_transaction(transaction, &self.signer).await?;
+        Ok(self.web3.eth().send_raw_transaction(signed_tx.raw_transaction).await?)
+    }
+
+    fn cancel_request(&self, nonce: U256) -> TxRequest {
+        TxRequest::new(self.address(), Bytes::default(), format!("Cancel of nonce {}", nonce))
+    }
+
+    /// Poll the receipts of the transactions sent with the same nonce until one of them is mined, the node does not
+    /// know any of them anymore, or `wait` is over.
+    async fn poll_receipt(&self, sent: &[H256], label: &str, wait: Duration) -> Result<ReceiptPoll> {
+        let started = Instant::now();
+        loop {
+            let mut mined = None;
+            for tx_hash in sent {
+                if let Some(receipt) = self.web3.eth().transaction_receipt(*tx_hash).await? {
+                    mined = Some(receipt);
+                    break;
+                }
+            }
+
+            match mined {
+                Some(receipt) => {
+                    if let Some(block_number) = receipt.block_number {
+                        let latest = self.web3.eth().block_number().await?;
+                        let confirmations = latest.saturating_sub(block_number).as_u64() + 1;
+                        if confirmations >= self.settings.confirmations {
+                            return Ok(ReceiptPoll::Mined(Box::new(receipt)));
+                        }
+                        println!("{:?} -- {} -- {:?} -- {} / {} confirmations", Instant::now(), label, receipt.transaction_hash, confirmations, self.settings.confirmations);
+                        return Ok(ReceiptPoll::Confirming);
+                    }
+                }
+                None => {
+                    let mut known = false;
+                    for tx_hash in sent {
+                        known |= self.web3.eth().transaction(TransactionId::Hash(*tx_hash)).await?.is_some();
+                    }
+                    // Unknown because mined between the two calls, or really dropped : the next poll tells.
+                    if !known && self.web3.eth().transaction_receipt(*sent.last().unwrap()).await?.is_none() {
+                        return Ok(ReceiptPoll::Dropped);
+                    }
+                    println!("{:?} -- {} -- {:?}", Instant::now(), label, sent.last().unwrap());
+                }
+            }
+
+            if started.elapsed() >= wait {
+                return Ok(ReceiptPoll::Pending);
+            }
+            tokio::time::sleep(self.settings.poll_interval).await;
+        }
+    }
+
+    /// Decode the logs of the receipt emitted by the contracts registered with [`TxSender::with_abi`].
+    pub fn decode_events(&self, receipt: &TransactionReceipt) -> Vec<DecodedEvent> {
+        receipt.logs.iter().filter_map(|log| {
+            let (_, abi) = self.abis.iter().find(|(address, _)| *address == log.address)?;
+            let topic = log.topics.first()?;
+            let event = abi.events().find(|event| event.signature() == *topic)?;
+            let raw = RawLog { topics: log.topics.clone(), data: log.data.0.clone() };
+            let decoded = event.parse_log(raw).ok()?;
+            Some(DecodedEvent { address: log.address, name: event.name.clone(), params: decoded.params })
+        }).collect()
+    }
+
+    /// Replay a reverted transaction with `eth_call` on the state of its block, to get the revert reason.
+    async fn replay_revert_reason(&self, request: &TxRequest, receipt: &TransactionReceipt) -> Option<String> {
+        let call = CallRequest {
+            from: Some(self.address()),
+            to: Some(request.to),
+            value: Some(request.value),
+            data: Some(request.data.clone()),
+            ..CallRequest::default()
+        };
+        // The state at the end of the parent block is the one the transaction ran on (minus the earlier txs of its block).
+        let block = receipt.block_number.map(|number| BlockId::Number(BlockNumber::Number(number.saturating_sub(U64::one()))));
+        match self.web3.eth().call(call, block).await {
+            Ok(_) => None,
+            Err(err) => revert_reason(&err),
+        }
+    }
+}
+
+/// Whether sending a transaction again failed because the node still holds one with the same nonce.
+fn is_still_pending(err: &NovaxError) -> bool {
+    match err {
+        NovaxError::Rpc(web3::Error::Rpc(rpc)) => {
+            let message = rpc.message.to_lowercase();
+            message.contains("already known") || message.contains("known transaction") || message.contains("underpriced")
+        }
+        _ => false,
+    }
+}
@@ -1,18 +1,24 @@
 use serde::Deserialize;
 use serde::Serialize;
-use web3::{Web3, Error};
+use web3::Web3;
 use web3::transports::WebSocket;
-use web3::ethabi::{Address, Token};
+use web3::ethabi::Address;
 use web3::types::{Bytes, BlockNumber};
-use web3::contract::{Contract, Options};
 use web3::types::CallRequest;
 use web3::ethabi::ethereum_types::{H160, U256};
+use web3::ethabi::{self, ParamType, Token};
+use crate::contracts::Structure;
+use crate::error::{NovaxError, Result};
+use crate::fees::GasFees;
 use std::time::Duration;
-use std::thread;
+
+/// Selector of `Error(string)`, the payload of `revert("reason")`.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct ResponseApi {
-    pub planetNo: String,
+    #[serde(rename = "planetNo")]
+    pub planet_no: String,
     pub coordinate: String,
     pub description: String,
     pub external_url: String,
@@ -37,82 +43,259 @@ pub struct Attribute {
     pub value: u32,
 }
 
+impl ResponseApi {
+    /// Current level of a structure of the planet.
+    pub fn structure_level(&self, structure: Structure) -> u32 {
+        match structure {
+            Structure::Solar => self.attributes.attribute_0.value,
+            Structure::Mine => self.attributes.attribute_1.value,
+            Structure::Crystal => self.attributes.attribute_2.value,
+        }
+    }
+}
+
 
-pub async fn instantiate_contract(web3: &Web3<WebSocket>, contract_address: &Address, abi_path: &str) -> Contract<WebSocket> {
-    let vec = std::fs::read(abi_path).unwrap();
-    Contract::from_json(
-        web3.eth(),
-        *contract_address,
-        vec.as_slice(),
-    ).unwrap()
-}
-
-pub async fn get_web3(avalanche_go_url: &str) -> Web3<WebSocket> {
-    let ws = web3::transports::WebSocket::new(avalanche_go_url)
-        .await
-        .unwrap();
-    web3::Web3::new(ws)
-}
-
-pub async fn get_gas_usage_estimation(wallet_address: H160, mut gas_price: U256, web3: &Web3<WebSocket>, game_contract: &Contract<WebSocket>, bytes: &Bytes) -> U256 {
-    let mut estimated_gas_price: U256 = U256::from(0);
-    let mut iteration = 0;
-    while iteration < 10 {
-        match web3.eth().estimate_gas(
-            CallRequest {
-                from: Some(wallet_address),
-                to: Some(game_contract.address()),
-                gas: None,
-                gas_price: Some(gas_price),
-                value: None,
-                data: Some(bytes.clone()),
-                transaction_type: None,
-                access_list: None,
-            },
-            None).await {
-            Ok(gas_usage) => { estimated_gas_price = gas_usage; break; },
-            Err(err) => { println!("Iteration {} / 10 -- Error while estimating gas usage for this call on contract {:?} -- Error message : {:?}", iteration,game_contract.address(), err); iteration = iteration + 1; thread::sleep(Duration::new(5,0)) },
+pub async fn get_web3(avalanche_go_url: &str) -> Result<Web3<WebSocket>> {
+    let ws = web3::transports::WebSocket::new(avalanche_go_url).await?;
+    Ok(web3::Web3::new(ws))
+}
+
+/// Gas used by the call, estimated by the node.
+///
+/// Only the errors that may go away by asking again (transport errors, node overloaded or lagging, base fee above
+/// the max fee) are retried. A revert or any other error of the node fails right away, and a wallet that cannot pay
+/// for the value and gas of the call fails with [`NovaxError::InsufficientFunds`].
+pub async fn get_gas_usage_estimation(wallet_address: H160, fees: &GasFees, web3: &Web3<WebSocket>, to: Address, bytes: &Bytes, value: U256) -> Result<U256> {
+    let call = CallRequest {
+        from: Some(wallet_address),
+        to: Some(to),
+        data: Some(bytes.clone()),
+        value: Some(value),
+        ..CallRequest::default()
+    };
+    let mut priced_call = call.clone();
+    fees.apply_to_call(&mut priced_call);
+
+    let mut last_error = None;
+    for iteration in 0..10 {
+        match web3.eth().estimate_gas(priced_call.clone(), None).await {
+            Ok(gas_usage) => return Ok(gas_usage),
+            // A revert will not go away by asking again, only transport and fee errors are worth retrying.
+            Err(err) if is_revert(&err) => {
+                let reason = revert_reason(&err).unwrap_or_else(|| "no reason given".to_string());
+                return Err(NovaxError::GasEstimation(format!("the call reverts : {}", reason)));
+            }
+            Err(err) if is_insufficient_funds(&err) => {
+                let available = web3.eth().balance(wallet_address, None).await?;
+                // Without fees the node only checks the value, which tells how much gas the call needs.
+                let gas = web3.eth().estimate_gas(call, None).await.unwrap_or_default();
+                return Err(NovaxError::InsufficientFunds { needed: value.saturating_add(gas.saturating_mul(fees.max_price())), available });
+            }
+            Err(err) if !is_transient(&err) => return Err(NovaxError::GasEstimation(format!("the node refused the call : {}", err))),
+            Err(err) => {
+                // On stderr, not to break the JSON reports printed on stdout.
+                eprintln!("Iteration {} / 10 -- Error while estimating gas usage for this call on contract {:?} -- Error message : {:?}", iteration, to, err);
+                last_error = Some(err);
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
         }
     }
 
-    if iteration == 10 && estimated_gas_price == U256::from(0) {
-        panic!("Failed to estimate gas usage . Probably this is due to your gas price being too low for the current network base fee. Try later or increase gas price !")
-    }
-    estimated_gas_price
-}
-
-pub async fn get_current_nonce(wallet_address: H160, web3: &Web3<WebSocket>) -> u64 {
-    let nonce = web3.eth().transaction_count(wallet_address, Option::from(BlockNumber::Pending)).await.unwrap();
-    let u64_nonce = nonce.as_u64();
-    u64_nonce
-}
-
-pub async fn fetch_current_resources(wallet_address: H160, iron_contract: &Contract<WebSocket>, solar_contract: &Contract<WebSocket>, crystal_contract: &Contract<WebSocket>, upgrade_cost: &Vec<U256>) -> (U256, U256, U256, f64, f64, f64, f64, f64, f64) {
-    let wallet_iron_amount_future = iron_contract.query("balanceOf", Token::Address(wallet_address), None, Options::default(), None);
-    let wallet_iron_amount: U256 = wallet_iron_amount_future.await.unwrap();
-    let solar_amount_future = solar_contract.query("balanceOf", Token::Address(wallet_address), None, Options::default(), None);
-    let solar_amount: U256 = solar_amount_future.await.unwrap();
-    let crystal_amount_future = crystal_contract.query("balanceOf", Token::Address(wallet_address), None, Options::default(), None);
-    let crystal_amount: U256 = crystal_amount_future.await.unwrap();
-
-    let iron_amount_decimals = (wallet_iron_amount.as_u128() as f64
-        / (10_u64.pow(18 as u32)) as f64)
-        as f64;
-    let solar_amount_decimals = (solar_amount.as_u128() as f64
-        / (10_u64.pow(18 as u32)) as f64)
-        as f64;
-    let crystal_amount_decimals = (crystal_amount.as_u128() as f64
-        / (10_u64.pow(18 as u32)) as f64)
-        as f64;
-
-    let upgrade_iron_amount_decimals = (upgrade_cost.get(1).unwrap().as_u128() as f64
-        / (10_u64.pow(18 as u32)) as f64)
-        as f64;
-    let upgrade_solar_amount_decimals = (upgrade_cost.get(0).unwrap().as_u128() as f64
-        / (10_u64.pow(18 as u32)) as f64)
-        as f64;
-    let upgrade_crystal_amount_decimals = (upgrade_cost.get(2).unwrap().as_u128() as f64
-        / (10_u64.pow(18 as u32)) as f64)
-        as f64;
-    (wallet_iron_amount, solar_amount, crystal_amount, iron_amount_decimals, solar_amount_decimals, crystal_amount_decimals, upgrade_iron_amount_decimals, upgrade_solar_amount_decimals, upgrade_crystal_amount_decimals)
+    Err(NovaxError::GasEstimation(format!("{:?}. Probably this is due to your max fee being too low for the current network base fee. Try later or increase it !", last_error)))
+}
+
+pub async fn get_current_nonce(wallet_address: H160, web3: &Web3<WebSocket>) -> Result<u64> {
+    let nonce = web3.eth().transaction_count(wallet_address, Option::from(BlockNumber::Pending)).await?;
+    Ok(nonce.as_u64())
+}
+
+/// Extract the revert reason from the error of a failed `eth_call` / `eth_estimateGas`.
+pub fn revert_reason(err: &web3::Error) -> Option<String> {
+    if let web3::Error::Rpc(rpc) = err {
+        if let Some(reason) = rpc.data.as_ref().and_then(|data| data.as_str()).and_then(decode_revert_data) {
+            return Some(reason);
+        }
+        if let Some(reason) = rpc.message.strip_prefix("execution reverted: ") {
+            return Some(reason.to_string());
+        }
+    }
+    None
+}
+
+/// Whether a failed `eth_call` / `eth_estimateGas` was reverted by the contract, with or without a reason, as opposed
+/// to a transport or node error.
+pub fn is_revert(err: &web3::Error) -> bool {
+    match err {
+        web3::Error::Rpc(rpc) => revert_reason(err).is_some() || rpc.message.starts_with("execution reverted"),
+        _ => false,
+    }
+}
+
+/// Whether a failed call was refused because the wallet cannot pay for its value and gas.
+pub fn is_insufficient_funds(err: &web3::Error) -> bool {
+    match err {
+        web3::Error::Rpc(rpc) => rpc.message.to_lowercase().contains("insufficient funds"),
+        _ => false,
+    }
+}
+
+/// Whether a failed call may succeed when sent again : the node could not be reached, was overloaded or lagging, or
+/// the base fee was above the max fee of the call.
+pub fn is_transient(err: &web3::Error) -> bool {
+    match err {
+        web3::Error::Unreachable | web3::Error::Transport(_) | web3::Error::Io(_) => true,
+        web3::Error::Rpc(rpc) => {
+            let message = rpc.message.to_lowercase();
+            ["header not found", "timeout", "timed out", "too many requests", "rate limit", "busy", "try again", "less than block base fee"]
+                .iter()
+                .any(|transient| message.contains(transient))
+        }
+        _ => false,
+    }
+}
+
+/// Whether a failed `eth_estimateGas` ran out of gas, or needed more than the gas limit of a block (or of the call).
+pub fn is_out_of_gas(err: &web3::Error) -> bool {
+    match err {
+        web3::Error::Rpc(rpc) => {
+            let message = rpc.message.to_lowercase();
+            message.contains("out of gas") || message.contains("gas limit") || message.contains("gas required exceeds")
+        }
+        _ => false,
+    }
+}
+
+/// Decode the ABI encoded `Error(string)` returned by a reverted call.
+pub fn decode_revert_data(data: &str) -> Option<String> {
+    let bytes = hex::decode(data.trim_start_matches("0x")).ok()?;
+    if bytes.len() < 4 || bytes[..4] != ERROR_SELECTOR {
+        return None;
+    }
+    match ethabi::decode(&[ParamType::String], &bytes[4..]).ok()?.pop()? {
+        Token::String(reason) => Some(reason),
+        _ => None,
+    }
+}
+
+/// Parse a decimal amount of an 18 decimals token (the resources, AVAX), e.g. `12.5`, into its base unit.
+pub fn parse_decimals(value: &str) -> Option<U256> {
+    let (units, fraction) = value.trim().split_once('.').unwrap_or((value.trim(), ""));
+    if fraction.len() > 18 || (units.is_empty() && fraction.is_empty()) || !(units.chars().chain(fraction.chars())).all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let units = if units.is_empty() { U256::zero() } else { U256::from_dec_str(units).ok()? };
+    let fraction = if fraction.is_empty() { U256::zero() } else { U256::from_dec_str(&format!("{:0<18}", fraction)).ok()? };
+    units.checked_mul(U256::exp10(18))?.checked_add(fraction)
+}
+
+/// Exact decimal representation of an amount of an 18 decimals token, e.g. `12.5`, the inverse of [`parse_decimals`].
+pub fn format_decimals(amount: U256) -> String {
+    let unit = U256::exp10(18);
+    let fraction = format!("{:0>18}", (amount % unit).to_string());
+    match fraction.trim_end_matches('0') {
+        "" => (amount / unit).to_string(),
+        fraction => format!("{}.{}", amount / unit, fraction),
+    }
+}
+
+/// Convert an amount of an 18 decimals token (the resources, AVAX) to a float, for display and price estimates.
+///
+/// The conversion goes through [`format_decimals`], so it does not panic on amounts above `u128::MAX` (like an
+/// unlimited allowance), it only loses precision.
+pub fn to_decimals(amount: U256) -> f64 {
+    format_decimals(amount).parse().unwrap_or(f64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(units: u64) -> U256 {
+        U256::from(units) * U256::exp10(18)
+    }
+
+    fn rpc_error(message: &str) -> web3::Error {
+        web3::Error::Rpc(serde_json::from_value(serde_json::json!({ "code": -32000, "message": message })).unwrap())
+    }
+
+    #[test]
+    fn only_transient_errors_are_worth_retrying() {
+        for message in ["header not found", "request timed out", "429 Too Many Requests", "max fee per gas less than block base fee: maxFeePerGas: 1, baseFee: 25000000000"] {
+            assert!(is_transient(&rpc_error(message)), "{:?} was not transient", message);
+        }
+        assert!(is_transient(&web3::Error::Unreachable));
+        for message in ["execution reverted", "invalid argument 0: json: cannot unmarshal", "insufficient funds for gas * price + value", "method not found"] {
+            assert!(!is_transient(&rpc_error(message)), "{:?} was transient", message);
+        }
+    }
+
+    #[test]
+    fn insufficient_funds_are_recognized() {
+        assert!(is_insufficient_funds(&rpc_error("insufficient funds for gas * price + value: address 0x01 have 0 want 1")));
+        assert!(!is_insufficient_funds(&rpc_error("execution reverted")));
+        assert!(!is_insufficient_funds(&web3::Error::Unreachable));
+    }
+
+    #[test]
+    fn out_of_gas_errors_are_told_apart_from_other_node_errors() {
+        for message in ["out of gas", "gas required exceeds allowance (8000000)", "exceeds block gas limit", "intrinsic gas too low: have 0, want 21000 (supplied gas limit 0)"] {
+            assert!(is_out_of_gas(&rpc_error(message)), "{:?} was not out of gas", message);
+        }
+        for message in ["execution reverted", "header not found", "insufficient funds for gas * price + value"] {
+            assert!(!is_out_of_gas(&rpc_error(message)), "{:?} was out of gas", message);
+        }
+        assert!(!is_out_of_gas(&web3::Error::Unreachable));
+    }
+
+    #[test]
+    fn parse_decimals_reads_units_and_fractions() {
+        assert_eq!(parse_decimals("12"), Some(tokens(12)));
+        assert_eq!(parse_decimals("12.5"), Some(tokens(12) + U256::exp10(17) * 5));
+        assert_eq!(parse_decimals(" 0.000000000000000001 "), Some(U256::one()));
+        assert_eq!(parse_decimals("0"), Some(U256::zero()));
+    }
+
+    #[test]
+    fn parse_decimals_accepts_a_leading_or_trailing_dot() {
+        assert_eq!(parse_decimals(".5"), Some(U256::exp10(17) * 5));
+        assert_eq!(parse_decimals("5."), Some(tokens(5)));
+    }
+
+    #[test]
+    fn parse_decimals_rejects_invalid_amounts() {
+        for value in ["", ".", "abc", "-1", "1.2.3", "1e18", "1,5", "0.0000000000000000001"] {
+            assert_eq!(parse_decimals(value), None, "{:?} was accepted", value);
+        }
+    }
+
+    #[test]
+    fn parse_decimals_rejects_overflows() {
+        let max_units = (U256::MAX / U256::exp10(18)).to_string();
+        assert!(parse_decimals(&max_units).is_some());
+        assert_eq!(parse_decimals(&format!("{}0", max_units)), None);
+        assert_eq!(parse_decimals(&U256::MAX.to_string()), None);
+        assert_eq!(parse_decimals(&format!("1{}", U256::MAX)), None);
+    }
+
+    #[test]
+    fn format_decimals_is_exact() {
+        assert_eq!(format_decimals(U256::zero()), "0");
+        assert_eq!(format_decimals(tokens(12)), "12");
+        assert_eq!(format_decimals(tokens(12) + U256::exp10(17) * 5), "12.5");
+        assert_eq!(format_decimals(U256::one()), "0.000000000000000001");
+        assert_eq!(format_decimals(U256::MAX), "115792089237316195423570985008687907853269984665640564039457.584007913129639935");
+    }
+
+    #[test]
+    fn format_decimals_round_trips_with_parse_decimals() {
+        for amount in [U256::zero(), U256::one(), tokens(1), tokens(12) + 5, U256::exp10(17) * 25, U256::MAX] {
+            assert_eq!(parse_decimals(&format_decimals(amount)), Some(amount));
+        }
+    }
+
+    #[test]
+    fn to_decimals_does_not_panic_above_u128() {
+        assert_eq!(to_decimals(tokens(3) / 2), 1.5);
+        assert!(to_decimals(U256::MAX) > 1e59);
+    }
 }